@@ -4,10 +4,9 @@
 extern crate libexif;
 
 use std::env;
-use std::io;
 use std::path::Path;
 
-fn dump_exif<P: AsRef<Path>>(file_name: P) -> io::Result<()> {
+fn dump_exif<P: AsRef<Path>>(file_name: P) -> Result<(), libexif::Error> {
     let data = libexif::Data::open(file_name.as_ref())?;
 
     println!("EXIF data for {:?}", file_name.as_ref());
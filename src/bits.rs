@@ -5,6 +5,7 @@ use libexif_sys::*;
 
 /// Defines the byte order of binary values.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ByteOrder {
     /// Most significant bytes come first.
     ///
@@ -40,6 +41,7 @@ impl Into<ExifByteOrder> for ByteOrder {
 
 /// Defines the encoding used to represent EXIF data.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DataEncoding {
     Chunky,
     Planar,
@@ -77,6 +79,7 @@ impl Into<ExifDataType> for DataEncoding {
 
 /// Options that affect the behavior of [`Data`](struct.Data.html).
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DataOption {
     /// Act as though unknown tags don't exist.
     IgnoreUnknownTags,
@@ -119,6 +122,7 @@ impl Into<ExifDataOption> for DataOption {
 
 /// EXIF tag data formats.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DataType {
     /// Tag contains text.
     Text,
@@ -189,6 +193,7 @@ impl Into<ExifFormat> for DataType {
 ///
 /// An image file directory (IFD) is a group of related EXIF tags.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IFD {
     /// IFD contents describe the primary image.
     Image,
@@ -231,6 +236,7 @@ impl Into<ExifIfd> for IFD {
 
 /// Requirement specificatoins for standard EXIF tags.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SupportLevel {
     /// EXIF tag is mandatory for the given IFD.
     Required,
@@ -0,0 +1,209 @@
+use crate::bits::*;
+use crate::data::Data;
+use crate::tag::Tag;
+use crate::value::Value;
+
+/// Value of `ISOSpeedRatings` indicating that the sensitivity does not fit,
+/// in which case it may be recorded in `ISOSpeed`.
+const ISO_OVERFLOW: u16 = 65535;
+
+/// Summary of the settings used to capture an image.
+///
+/// Fields are `None` if the corresponding tags are missing or invalid.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CameraSettings {
+    /// Manufacturer of the camera, from `Make`.
+    pub make: Option<String>,
+    /// Model of the camera, from `Model`.
+    pub model: Option<String>,
+    /// Exposure time in seconds, from `ExposureTime`.
+    pub exposure_time: Option<f64>,
+    /// F-number of the aperture, from `FNumber`.
+    pub f_number: Option<f64>,
+    /// ISO sensitivity, from `ISOSpeedRatings` or `ISOSpeed`.
+    pub iso: Option<u32>,
+    /// Focal length of the lens in millimeters, from `FocalLength`.
+    pub focal_length: Option<f64>,
+    /// Exposure bias in EV, from `ExposureBiasValue`.
+    pub exposure_bias: Option<f64>,
+    /// Status of the flash, from `Flash`.
+    pub flash: Option<Flash>,
+    /// Metering mode, from `MeteringMode`.
+    pub metering_mode: Option<MeteringMode>,
+    /// Lens model, from `LensModel`.
+    pub lens: Option<String>,
+}
+
+impl CameraSettings {
+    /// Gather the settings from IFD0 and the EXIF IFD.
+    pub(crate) fn from_data(data: &Data) -> CameraSettings {
+        let Ok(byte_order) = data.byte_order() else {
+            return CameraSettings::default();
+        };
+        let value = |ifd: IFD, tag: Tag| data.get(ifd, tag)?.value(byte_order).ok();
+        let text = |ifd: IFD, tag: Tag| match value(ifd, tag)? {
+            Value::Text(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+            _ => None,
+        };
+        let number = |tag: Tag| match value(IFD::EXIF, tag)? {
+            Value::URational(v) => v.first()?.to_f64(),
+            Value::IRational(v) => v.first()?.to_f64(),
+            _ => None,
+        };
+        let short = |tag: Tag| match value(IFD::EXIF, tag)? {
+            Value::U16(v) => v.first().copied(),
+            _ => None,
+        };
+        let iso = match short(Tag::ISO_SPEED_RATINGS) {
+            Some(ISO_OVERFLOW) | None => match value(IFD::EXIF, Tag::ISO_SPEED) {
+                Some(Value::U32(v)) => v.first().copied(),
+                _ => short(Tag::ISO_SPEED_RATINGS).map(u32::from),
+            },
+            Some(iso) => Some(u32::from(iso)),
+        };
+        CameraSettings {
+            make: text(IFD::Image, Tag::MAKE),
+            model: text(IFD::Image, Tag::MODEL),
+            exposure_time: number(Tag::EXPOSURE_TIME),
+            f_number: number(Tag::FNUMBER),
+            iso,
+            focal_length: number(Tag::FOCAL_LENGTH),
+            exposure_bias: number(Tag::EXPOSURE_BIAS_VALUE),
+            flash: short(Tag::FLASH).map(Flash),
+            metering_mode: short(Tag::METERING_MODE).map(MeteringMode::from),
+            lens: text(IFD::EXIF, Tag::LENS_MODEL),
+        }
+    }
+
+    /// Format the exposure time, such as `1/250 s`, `0.8 s` or `2.5 s`.
+    ///
+    /// Exposure times of up to 1/1.5 s, which include every fraction `1/n`,
+    /// are written as fractions. Longer ones, such as 0.8 s, are written as
+    /// decimals.
+    pub fn exposure_time_text(&self) -> Option<String> {
+        let seconds = self.exposure_time.filter(|seconds| *seconds > 0.0)?;
+        let reciprocal = 1.0 / seconds;
+        if reciprocal >= 1.5 {
+            Some(format!("1/{} s", format_decimal(reciprocal, 0)))
+        } else {
+            Some(format!("{} s", format_decimal(seconds, 1)))
+        }
+    }
+
+    /// Format the f-number, such as `f/2.8` or `f/8`.
+    pub fn f_number_text(&self) -> Option<String> {
+        Some(format!("f/{}", format_decimal(self.f_number?, 1)))
+    }
+
+    /// Format the ISO sensitivity, such as `ISO 100`.
+    pub fn iso_text(&self) -> Option<String> {
+        Some(format!("ISO {}", self.iso?))
+    }
+
+    /// Format the focal length, such as `50 mm` or `4.5 mm`.
+    pub fn focal_length_text(&self) -> Option<String> {
+        Some(format!("{} mm", format_decimal(self.focal_length?, 1)))
+    }
+
+    /// Format the exposure bias, such as `+0.7 EV`, `0 EV` or `-1 EV`.
+    pub fn exposure_bias_text(&self) -> Option<String> {
+        let bias = format_decimal(self.exposure_bias?, 1);
+        if bias == "0" || bias.starts_with('-') {
+            Some(format!("{} EV", bias))
+        } else {
+            Some(format!("+{} EV", bias))
+        }
+    }
+}
+
+/// Format a number with at most the given number of decimals, without
+/// trailing zeros.
+fn format_decimal(value: f64, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    let text = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &text
+    };
+    // avoid "-0" for small negative values
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Status of the flash when the image was captured, as recorded in the
+/// `Flash` tag.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Flash(pub u16);
+
+impl Flash {
+    /// Returns `true` if the flash fired.
+    pub fn fired(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    /// Returns the flash mode.
+    pub fn mode(&self) -> FlashMode {
+        match (self.0 >> 3) & 0x03 {
+            1 => FlashMode::On,
+            2 => FlashMode::Off,
+            3 => FlashMode::Auto,
+            _ => FlashMode::Unknown,
+        }
+    }
+
+    /// Returns `true` if the camera has a flash.
+    pub fn is_present(&self) -> bool {
+        self.0 & 0x20 == 0
+    }
+
+    /// Returns `true` if red-eye reduction was enabled.
+    pub fn red_eye_reduction(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+}
+
+/// Mode of the flash.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum FlashMode {
+    Unknown,
+    /// The flash is forced to fire.
+    On,
+    /// The flash is suppressed.
+    Off,
+    /// The camera decides whether the flash fires.
+    Auto,
+}
+
+/// Metering mode used to determine the exposure, as recorded in the
+/// `MeteringMode` tag.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MeteringMode {
+    Unknown,
+    Average,
+    CenterWeightedAverage,
+    Spot,
+    MultiSpot,
+    Pattern,
+    Partial,
+    /// Any other metering mode, including values reserved by the
+    /// specification.
+    Other,
+}
+
+impl From<u16> for MeteringMode {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => MeteringMode::Unknown,
+            1 => MeteringMode::Average,
+            2 => MeteringMode::CenterWeightedAverage,
+            3 => MeteringMode::Spot,
+            4 => MeteringMode::MultiSpot,
+            5 => MeteringMode::Pattern,
+            6 => MeteringMode::Partial,
+            _ => MeteringMode::Other,
+        }
+    }
+}
@@ -2,26 +2,31 @@
 // Copyright (c) 2016 David Cuddeback
 //
 use crate::bits::*;
-use crate::entry::Entry;
+use crate::entry::{Entry, EntryMut};
 use crate::internal::*;
+use crate::tag::Tag;
+use crate::value::Value;
 use libexif_sys::*;
-use std::mem;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr::NonNull;
 use std::slice;
 
 /// Container for all EXIF data in a single [IFD](enum.IFD.html).
 pub struct Content<'a> {
-    inner: &'a mut ExifContent,
+    inner: NonNull<ExifContent>,
+    marker: PhantomData<&'a ExifContent>,
 }
 
 impl<'a> Content<'a> {
     /// Return the IFD for the content.
     pub fn ifd(&self) -> Result<IFD, super::Error> {
-        IFD::try_from(unsafe { exif_content_get_ifd(self.inner as *const _ as *mut _) })
+        IFD::try_from(unsafe { exif_content_get_ifd(self.inner.as_ptr()) })
     }
 
     /// Return the number of [entries](struct.Entry.html) in the IFD.
     pub fn len(&self) -> usize {
-        self.inner.count as usize
+        self.raw().count as usize
     }
 
     /// Iterate over the [entries](struct.Entry.html) in the IFD.
@@ -34,17 +39,191 @@ impl<'a> Content<'a> {
         } else {
             Entries {
                 entries: unsafe {
-                    slice::from_raw_parts(self.inner.entries, self.inner.count as usize)
+                    slice::from_raw_parts(self.raw().entries, self.raw().count as usize)
                 },
                 index: 0,
             }
         }
     }
+
+    /// Return the entry for the given tag, if the IFD contains one.
+    pub fn get<'b>(&'b self, tag: Tag) -> Option<Entry<'b>> {
+        let entry = unsafe { exif_content_get_entry(self.inner.as_ptr(), tag.code()) };
+        if entry.is_null() {
+            None
+        } else {
+            Some(Entry::from_libexif(unsafe { &*entry }))
+        }
+    }
+
+    /// Build a value for the given tag that the IFD would accept.
+    ///
+    /// `build` is asked for a value of the data type of an existing entry
+    /// first, as some tags may be either SHORT or LONG, then of the type
+    /// prescribed by the EXIF specification and finally of the fallback type.
+    /// The first value allowed for the tag is returned.
+    #[cfg(any(feature = "json", feature = "xmp"))]
+    pub(crate) fn build_value<F>(&self, tag: Tag, fallback: DataType, build: F) -> Option<Value>
+    where
+        F: Fn(DataType) -> Option<Value>,
+    {
+        let ifd = unsafe { exif_content_get_ifd(self.inner.as_ptr()) };
+        let existing = self.get(tag).and_then(|entry| entry.data_type().ok());
+        let spec = crate::entry::spec_data_type(tag.code(), ifd);
+        [existing, spec, Some(fallback)]
+            .into_iter()
+            .flatten()
+            .filter_map(build)
+            .find(|value| crate::entry::allows_data_type(tag, ifd, value.data_type()))
+    }
+
+    fn raw(&self) -> &ExifContent {
+        unsafe { self.inner.as_ref() }
+    }
+}
+
+/// Container for all EXIF data in a single [IFD](enum.IFD.html), borrowed for
+/// modification.
+///
+/// Dereferences to [`Content`](struct.Content.html) for reading the entries.
+/// It is returned by [`Data::content_mut()`](struct.Data.html#method.content_mut),
+/// so entries can only be added or removed while the EXIF data is borrowed
+/// mutably. In particular, no entry can be removed through a shared reference
+/// to the data:
+///
+/// ```compile_fail
+/// # use libexif::{Data, IFD, Tag};
+/// fn strip_artist(data: &Data) {
+///     data.content(IFD::Image).remove(Tag::ARTIST);
+/// }
+/// ```
+pub struct ContentMut<'a> {
+    content: Content<'a>,
+    /// The allocator of the data containing the IFD.
+    mem: *mut ExifMem,
+    marker: PhantomData<&'a mut ExifContent>,
+}
+
+impl<'a> ContentMut<'a> {
+    /// Return the entry for the given tag for modification, if the IFD
+    /// contains one.
+    pub fn get_mut<'b>(&'b mut self, tag: Tag) -> Option<EntryMut<'b>> {
+        let entry = unsafe { exif_content_get_entry(self.as_ptr(), tag.code()) };
+        if entry.is_null() {
+            None
+        } else {
+            Some(EntryMut::from_libexif((unsafe { &mut *entry }, self.mem)))
+        }
+    }
+
+    /// Add an entry for the given tag to the IFD.
+    ///
+    /// The new entry is initialized with the default value prescribed by the
+    /// EXIF specification, if any. If the IFD already contains an entry for
+    /// the tag, that entry is returned unchanged.
+    pub fn insert<'b>(&'b mut self, tag: Tag) -> Result<EntryMut<'b>, super::Error> {
+        let (entry, _) = self.insert_entry(tag.code())?;
+        Ok(EntryMut::from_libexif((unsafe { &mut *entry }, self.mem)))
+    }
+
+    /// Add an entry for the given tag with the given value to the IFD.
+    ///
+    /// If the IFD already contains an entry for the tag, its value is
+    /// replaced. The value is encoded using the byte order of the EXIF data
+    /// containing the IFD.
+    pub fn insert_value<'b>(
+        &'b mut self,
+        tag: Tag,
+        value: Value,
+    ) -> Result<EntryMut<'b>, super::Error> {
+        let byte_order =
+            ByteOrder::try_from(unsafe { exif_data_get_byte_order(self.raw().parent) })?;
+        let (entry, created) = self.insert_entry(tag.code())?;
+        let mut entry = EntryMut::from_libexif((unsafe { &mut *entry }, self.mem));
+        if let Err(err) = entry.set_value(value, byte_order) {
+            if created {
+                unsafe {
+                    exif_content_remove_entry(self.as_ptr(), entry.as_ptr());
+                }
+            }
+            return Err(err);
+        }
+        Ok(entry)
+    }
+
+    /// Remove the entry for the given tag from the IFD.
+    ///
+    /// Returns `true` if the IFD contained an entry for the tag.
+    pub fn remove(&mut self, tag: Tag) -> bool {
+        unsafe {
+            let entry = exif_content_get_entry(self.as_ptr(), tag.code());
+            if entry.is_null() {
+                false
+            } else {
+                exif_content_remove_entry(self.as_ptr(), entry);
+                true
+            }
+        }
+    }
+
+    /// Find or create the entry for a tag, returning whether it was created.
+    fn insert_entry(&mut self, tag: ExifTag) -> Result<(*mut ExifEntry, bool), super::Error> {
+        unsafe {
+            let entry = exif_content_get_entry(self.as_ptr(), tag);
+            if !entry.is_null() {
+                return Ok((entry, false));
+            }
+            // libexif allocates the entries of a data with the data's allocator
+            let entry = exif_entry_new_mem(self.mem);
+            if entry.is_null() {
+                return Err(super::Error::OutOfMemory);
+            }
+            // the tag must be set before adding, libexif refuses duplicate tags
+            (*entry).tag = tag;
+            exif_content_add_entry(self.as_ptr(), entry);
+            if (*entry).parent.is_null() {
+                exif_entry_unref(entry);
+                return Err(super::Error::OutOfMemory);
+            }
+            exif_entry_initialize(entry, tag);
+            // the content now holds its own reference to the entry
+            exif_entry_unref(entry);
+            Ok((entry, true))
+        }
+    }
+
+    fn as_ptr(&mut self) -> *mut ExifContent {
+        self.content.inner.as_ptr()
+    }
+}
+
+impl<'a> Deref for ContentMut<'a> {
+    type Target = Content<'a>;
+
+    fn deref(&self) -> &Content<'a> {
+        &self.content
+    }
 }
 
-impl<'a> FromLibExif<&'a mut ExifContent> for Content<'a> {
-    fn from_libexif(content: &'a mut ExifContent) -> Content<'a> {
-        Content { inner: content }
+impl<'a> FromLibExif<&'a ExifContent> for Content<'a> {
+    fn from_libexif(content: &'a ExifContent) -> Content<'a> {
+        Content {
+            inner: NonNull::from(content),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a> FromLibExif<(&'a mut ExifContent, *mut ExifMem)> for ContentMut<'a> {
+    fn from_libexif((content, mem): (&'a mut ExifContent, *mut ExifMem)) -> ContentMut<'a> {
+        ContentMut {
+            content: Content {
+                inner: NonNull::from(content),
+                marker: PhantomData,
+            },
+            mem,
+            marker: PhantomData,
+        }
     }
 }
 
@@ -60,7 +239,7 @@ impl<'a> Iterator for Entries<'a> {
         if self.index < self.entries.len() {
             let entry = self.entries[self.index];
             self.index += 1;
-            Some(Entry::from_libexif(unsafe { mem::transmute(entry) }))
+            Some(Entry::from_libexif(unsafe { &*entry }))
         } else {
             None
         }
@@ -2,27 +2,117 @@
 // Copyright (c) 2016 David Cuddeback
 //
 use crate::bits::*;
-use crate::content::Content;
+use crate::camera::CameraSettings;
+use crate::content::{Content, ContentMut};
+#[cfg(feature = "chrono")]
+use crate::datetime::{DateTimeKind, ExifDateTime};
+use crate::diagnostic::{Diagnostic, Log};
+use crate::entry::{Entry, EntryMut};
+use crate::gps::GpsInfo;
 use crate::internal::*;
+use crate::jpeg;
+#[cfg(feature = "json")]
+use crate::json::{self, JsonMode};
 use crate::loader::Loader;
+use crate::maker_note::MakerNote;
+use crate::mem::{self as exif_mem, MemoryBudget};
+use crate::orientation::Orientation;
+use crate::tag::Tag;
+use crate::tiff;
+use crate::value::Value;
+#[cfg(feature = "xmp")]
+use crate::xmp;
+use libc::{c_uint, c_void};
 use libexif_sys::*;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
 use std::mem;
 use std::path::Path;
+use std::ptr;
 use std::slice;
 
+/// Header that precedes the TIFF structure in a raw EXIF block.
+const EXIF_HEADER: &[u8; 6] = b"Exif\0\0";
+
+/// Order in which the IFDs are laid out by the EXIF specification.
+const SPEC_ORDER: [IFD; 5] = [
+    IFD::Image,
+    IFD::EXIF,
+    IFD::GPS,
+    IFD::Interoperability,
+    IFD::Thumbnail,
+];
+
 /// Container for all EXIF data found in an image.
+///
+/// `Data` owns its libexif objects exclusively, so it can be moved to another
+/// thread. It is not `Sync`: libexif updates the data in place even for
+/// operations that take `&self`, such as [`to_bytes()`](#method.to_bytes).
+///
+/// Cloning `Data` makes a deep copy by serializing the data and loading it
+/// again, so changes to the copy never affect the original. The copy holds the
+/// same entries, thumbnail, maker note, encoding and options as the original,
+/// although the entries of each IFD are ordered by tag, as libexif does when
+/// serializing. The memory used by the copy is not charged to any
+/// [`MemoryBudget`](struct.MemoryBudget.html).
 pub struct Data {
     inner: &'static mut ExifData,
+    /// The allocator used by `inner`.
+    mem: *mut ExifMem,
+    /// Position of the MakerNote entry's value in the TIFF structure the data
+    /// was loaded from.
+    maker_note_offset: usize,
+    /// The options enabled for `inner`, which libexif has no getter for.
+    options: ExifDataOption,
+    log: Log,
 }
 
 impl FromLibExif<*mut ExifData> for Data {
     fn from_libexif(ptr: *mut ExifData) -> Data {
-        Data {
-            inner: unsafe { mem::transmute(ptr) },
+        // the default allocator is used unless another one is passed to libexif
+        Data::with_mem(ptr, unsafe { exif_mem_new_default() })
+    }
+}
+
+// The ExifData, its allocator and its log are referenced only by this value,
+// and libexif keeps no global mutable state, so moving them to another thread
+// is safe. Reference counts in libexif are not atomic, so no libexif object
+// may ever be shared with another Data or Loader.
+unsafe impl Send for Data {}
+
+impl Clone for Data {
+    fn clone(&self) -> Data {
+        let ptr = unsafe { exif_data_new() };
+        assert!(!ptr.is_null());
+        let mut copy = Data::from_libexif(ptr);
+        // load the serialized data verbatim, then apply the original options
+        copy.unset_option(DataOption::IgnoreUnknownTags);
+        copy.unset_option(DataOption::FollowSpecification);
+        let bytes = self.to_bytes();
+        if !bytes.is_empty() {
+            copy.load_raw(&bytes);
         }
+        for option in [
+            DataOption::IgnoreUnknownTags,
+            DataOption::FollowSpecification,
+            DataOption::DontChangeMakerNote,
+        ] {
+            let mask: ExifDataOption = option.into();
+            if self.options & mask != 0 {
+                copy.set_option(option);
+            } else {
+                copy.unset_option(option);
+            }
+        }
+        unsafe {
+            exif_data_set_data_type(
+                ptr,
+                exif_data_get_data_type(self.inner as *const _ as *mut _),
+            );
+        }
+        copy.log.take();
+        copy
     }
 }
 
@@ -30,31 +120,171 @@ impl Drop for Data {
     fn drop(&mut self) {
         unsafe {
             exif_data_unref(self.inner);
+            exif_mem_unref(self.mem);
         }
     }
 }
 
 impl Data {
+    /// Wrap EXIF data that was constructed with the given allocator, taking
+    /// over a reference to the allocator.
+    pub(crate) fn with_mem(ptr: *mut ExifData, mem: *mut ExifMem) -> Data {
+        let log = Log::new();
+        unsafe {
+            exif_data_log(ptr, log.as_ptr());
+        }
+        Data {
+            inner: unsafe { mem::transmute(ptr) },
+            mem,
+            maker_note_offset: 0,
+            // enabled by libexif for every new data
+            options: ExifDataOption_EXIF_DATA_OPTION_IGNORE_UNKNOWN_TAGS
+                | ExifDataOption_EXIF_DATA_OPTION_FOLLOW_SPECIFICATION,
+            log,
+        }
+    }
+
     /// Construct a new EXIF data container with EXIF data from a JPEG file.
-    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Data> {
-        let mut file = File::open(path)?;
-        let mut loader = Loader::new();
-        let mut buffer = Vec::<u8>::with_capacity(1024);
-        loop {
-            let mut read_buf =
-                unsafe { slice::from_raw_parts_mut(buffer.as_mut_ptr(), buffer.capacity()) };
-            let len = file.read(&mut read_buf)?;
-            unsafe {
-                buffer.set_len(len);
-            }
-            if !loader.write_data(&mut buffer) {
-                break;
-            }
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Data, super::Error> {
+        Data::from_reader(File::open(path)?)
+    }
+
+    /// Construct a new EXIF data container with EXIF data from a JPEG file,
+    /// along with the messages libexif reported while loading it.
+    ///
+    /// See [`take_diagnostics()`](#method.take_diagnostics).
+    pub fn open_with_diagnostics<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(Data, Vec<Diagnostic>), super::Error> {
+        let mut data = Data::open(path)?;
+        let diagnostics = data.take_diagnostics();
+        Ok((data, diagnostics))
+    }
+
+    /// Construct a new EXIF data container with EXIF data read from a JPEG
+    /// stream.
+    ///
+    /// Reading stops as soon as the EXIF data has been found, so the reader
+    /// may not be consumed entirely.
+    ///
+    /// Returns [`Error::NoExifFound`](enum.Error.html#variant.NoExifFound)
+    /// if the image has no EXIF data,
+    /// [`Error::UnsupportedContainer`](enum.Error.html#variant.UnsupportedContainer)
+    /// if the stream is not a JPEG image, and
+    /// [`Error::Truncated`](enum.Error.html#variant.Truncated) if the stream
+    /// ends within the EXIF data.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Data, super::Error> {
+        load_from(Loader::new(), reader)
+    }
+
+    /// Construct a new EXIF data container with EXIF data read from a JPEG
+    /// stream, limiting the resources used by libexif to the given budget.
+    ///
+    /// Returns [`Error::LimitExceeded`](enum.Error.html#variant.LimitExceeded)
+    /// if the budget is exceeded.
+    pub fn from_reader_with_budget<R: Read>(
+        reader: R,
+        budget: &MemoryBudget,
+    ) -> Result<Data, super::Error> {
+        load_from(Loader::with_budget(budget), reader)
+    }
+
+    /// Construct a new EXIF data container from a raw EXIF block.
+    ///
+    /// The block may start with the `Exif\0\0` header, as found in a JPEG
+    /// APP1 segment and as produced by [`to_bytes()`](#method.to_bytes), or
+    /// directly with the TIFF header.
+    ///
+    /// A well-formed block without any entries loads as empty data.
+    ///
+    /// Returns [`Error::NoExifFound`](enum.Error.html#variant.NoExifFound)
+    /// if the block has no EXIF or TIFF header.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Data, super::Error> {
+        let ptr = unsafe { exif_data_new() };
+        assert!(!ptr.is_null());
+        let mut data = Data::from_libexif(ptr);
+        data.load(bytes)?;
+        Ok(data)
+    }
+
+    /// Construct a new EXIF data container from a raw EXIF block, limiting
+    /// the resources used by libexif to the given budget.
+    ///
+    /// Returns [`Error::LimitExceeded`](enum.Error.html#variant.LimitExceeded)
+    /// if the budget is exceeded.
+    pub fn from_bytes_with_budget(
+        bytes: &[u8],
+        budget: &MemoryBudget,
+    ) -> Result<Data, super::Error> {
+        let mem = exif_mem::new_budget_mem();
+        let scope = budget.enter();
+        let ptr = unsafe { exif_data_new_mem(mem) };
+        if ptr.is_null() {
+            unsafe { exif_mem_unref(mem) };
+            return Err(super::Error::LimitExceeded);
+        }
+        let mut data = Data::with_mem(ptr, mem);
+        let loaded = data.load(bytes);
+        data.check_budget(budget, scope.exceeded())?;
+        loaded?;
+        Ok(data)
+    }
+
+    fn load(&mut self, bytes: &[u8]) -> Result<(), super::Error> {
+        self.load_raw(bytes);
+        // libexif silently leaves the data empty when it finds no EXIF header,
+        // which a well-formed block without any entries cannot be told from
+        if find_tiff(bytes).is_none() {
+            // libexif also finds the EXIF segment in a complete JPEG image
+            return Err(
+                if bytes.starts_with(EXIF_HEADER)
+                    || is_tiff(bytes)
+                    || bytes.starts_with(&[0xff, 0xd8])
+                {
+                    super::Error::NoExifFound
+                } else {
+                    super::Error::UnsupportedContainer
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Load a raw EXIF block, with or without the EXIF header, into this data.
+    pub(crate) fn load_raw(&mut self, bytes: &[u8]) {
+        let with_header;
+        let bytes = if is_tiff(bytes) {
+            with_header = [&EXIF_HEADER[..], bytes].concat();
+            &with_header[..]
+        } else {
+            bytes
+        };
+        unsafe {
+            exif_data_load_data(
+                self.inner as *const _ as *mut _,
+                bytes.as_ptr(),
+                bytes.len() as c_uint,
+            );
+        }
+        // libexif keeps this to itself, but maker notes locate their values
+        // relative to the TIFF structure
+        self.maker_note_offset = find_tiff(bytes)
+            .and_then(maker_note_offset)
+            .unwrap_or_default();
+    }
+
+    /// Check the loaded data against the limits of a budget.
+    pub(crate) fn check_budget(
+        &self,
+        budget: &MemoryBudget,
+        exceeded: bool,
+    ) -> Result<(), super::Error> {
+        let entries: usize = self.contents().map(|content| content.len()).sum();
+        if exceeded || entries > budget.max_entries() {
+            Err(super::Error::LimitExceeded)
+        } else {
+            Ok(())
         }
-        loader.data().ok_or(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "invalid EXIF data",
-        ))
     }
 
     /// Return the byte order in use by this EXIF data.
@@ -83,16 +313,20 @@ impl Data {
 
     /// Enable a data processing option.
     pub fn set_option(&mut self, option: DataOption) {
+        let mask: ExifDataOption = option.into();
         unsafe {
-            exif_data_set_option(self.inner, option.into());
+            exif_data_set_option(self.inner, mask);
         }
+        self.options |= mask;
     }
 
     /// Disable a data processing option.
     pub fn unset_option(&mut self, option: DataOption) {
+        let mask: ExifDataOption = option.into();
         unsafe {
-            exif_data_unset_option(self.inner, option.into());
+            exif_data_unset_option(self.inner, mask);
         }
+        self.options &= !mask;
     }
 
     /// Iterate over the contents of the EXIF data.
@@ -103,6 +337,299 @@ impl Data {
         }
     }
 
+    /// Serialize the EXIF data into a raw EXIF block.
+    ///
+    /// The returned bytes start with the `Exif\0\0` header followed by the
+    /// TIFF structure, encoded using the current [byte order](#method.byte_order)
+    /// and data options. This is the payload of a JPEG APP1 segment.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer: *mut u8 = ptr::null_mut();
+        let mut size: c_uint = 0;
+        unsafe {
+            exif_data_save_data(self.inner as *const _ as *mut _, &mut buffer, &mut size);
+            if buffer.is_null() {
+                return Vec::new();
+            }
+            let bytes = slice::from_raw_parts(buffer, size as usize).to_vec();
+            exif_mem_free(self.mem, buffer as *mut c_void);
+            bytes
+        }
+    }
+
+    /// Write a copy of a JPEG file with its EXIF data replaced by this data.
+    ///
+    /// The existing EXIF segment of the source image is replaced, or a new
+    /// one is inserted if there was none. All other segments and the image
+    /// data are copied unchanged. The source and destination may be the same
+    /// file.
+    pub fn write_to_jpeg<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        src: P,
+        dst: Q,
+    ) -> Result<(), super::Error> {
+        let jpeg = std::fs::read(src)?;
+        let output = self.write_to_jpeg_bytes(&jpeg)?;
+        std::fs::write(dst, output)?;
+        Ok(())
+    }
+
+    /// Return a copy of a JPEG image with its EXIF data replaced by this data.
+    ///
+    /// See [`write_to_jpeg()`](#method.write_to_jpeg) for details.
+    pub fn write_to_jpeg_bytes(&self, jpeg: &[u8]) -> Result<Vec<u8>, super::Error> {
+        let exif = self.to_bytes();
+        if exif.is_empty() {
+            // libexif only fails to serialize when it runs out of memory
+            return Err(super::Error::OutOfMemory);
+        }
+        jpeg::replace_exif(jpeg, &exif)
+    }
+
+    /// Return the JPEG thumbnail image stored with the EXIF data, if any.
+    pub fn thumbnail(&self) -> Option<&[u8]> {
+        if self.inner.data.is_null() || self.inner.size == 0 {
+            None
+        } else {
+            Some(unsafe { slice::from_raw_parts(self.inner.data, self.inner.size as usize) })
+        }
+    }
+
+    /// Replace the thumbnail image with the given JPEG image.
+    ///
+    /// The Compression tag of the thumbnail IFD is set to indicate a JPEG
+    /// thumbnail. The location and length of the thumbnail are recorded by
+    /// libexif when the data is saved, so any JPEGInterchangeFormat and
+    /// JPEGInterchangeFormatLength entries are removed.
+    pub fn set_thumbnail(&mut self, jpeg: Vec<u8>) -> Result<(), super::Error> {
+        if !jpeg.starts_with(&[0xff, 0xd8]) {
+            return Err(super::Error::InvalidThumbnail);
+        }
+        unsafe {
+            // the thumbnail is freed by libexif with the allocator of the data
+            let buffer = exif_mem_alloc(self.mem, jpeg.len() as ExifLong) as *mut u8;
+            if buffer.is_null() {
+                return Err(super::Error::OutOfMemory);
+            }
+            ptr::copy_nonoverlapping(jpeg.as_ptr(), buffer, jpeg.len());
+            exif_mem_free(self.mem, self.inner.data as *mut c_void);
+            self.inner.data = buffer;
+            self.inner.size = jpeg.len() as c_uint;
+        }
+        let mut content = self.content_mut(IFD::Thumbnail);
+        content.remove(Tag::JPEG_INTERCHANGE_FORMAT);
+        content.remove(Tag::JPEG_INTERCHANGE_FORMAT_LENGTH);
+        // 6 is JPEG compression
+        content.insert_value(Tag::COMPRESSION, Value::U16(vec![6]))?;
+        Ok(())
+    }
+
+    /// Remove the thumbnail image, along with the thumbnail IFD entries that
+    /// describe its format and location.
+    pub fn remove_thumbnail(&mut self) {
+        unsafe {
+            exif_mem_free(self.mem, self.inner.data as *mut c_void);
+        }
+        self.inner.data = ptr::null_mut();
+        self.inner.size = 0;
+        let mut content = self.content_mut(IFD::Thumbnail);
+        content.remove(Tag::COMPRESSION);
+        content.remove(Tag::JPEG_INTERCHANGE_FORMAT);
+        content.remove(Tag::JPEG_INTERCHANGE_FORMAT_LENGTH);
+    }
+
+    /// Return the maker note, if libexif was able to interpret it.
+    pub fn maker_note<'a>(&'a self) -> Option<MakerNote<'a>> {
+        let ptr = unsafe { exif_data_get_mnote_data(self.inner as *const _ as *mut _) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(MakerNote::new(self, ptr, self.maker_note_offset))
+        }
+    }
+
+    /// Return the orientation of the primary image.
+    ///
+    /// Returns `None` if the primary image has no `Orientation` entry, or if
+    /// the entry does not hold a valid orientation.
+    pub fn orientation(&self) -> Option<Orientation> {
+        let entry = self.get(IFD::Image, Tag::ORIENTATION)?;
+        match entry.value(self.byte_order().ok()?).ok()? {
+            Value::U16(values) => Orientation::try_from(*values.first()?).ok(),
+            _ => None,
+        }
+    }
+
+    /// Set the orientation of the primary image.
+    pub fn set_orientation(&mut self, orientation: Orientation) -> Result<(), super::Error> {
+        self.content_mut(IFD::Image)
+            .insert_value(Tag::ORIENTATION, Value::U16(vec![orientation.into()]))?;
+        Ok(())
+    }
+
+    /// Return the date and time of the given kind, combined with the
+    /// corresponding sub-second and offset tags if present.
+    ///
+    /// Returns `None` if the date and time tag is missing, blank or
+    /// malformed.
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self, kind: DateTimeKind) -> Option<ExifDateTime> {
+        ExifDateTime::from_data(self, kind)
+    }
+
+    /// Record a date and time of the given kind.
+    ///
+    /// Writes the date and time tag, and the corresponding `SubSecTime` and
+    /// `OffsetTime` tags. These are removed if the time has no fractional
+    /// seconds or no offset, respectively, so the tags never disagree.
+    #[cfg(feature = "chrono")]
+    pub fn set_date_time<T: Into<ExifDateTime>>(
+        &mut self,
+        kind: DateTimeKind,
+        date_time: T,
+    ) -> Result<(), super::Error> {
+        date_time.into().write_to(self, kind)
+    }
+
+    /// Return a summary of the settings used to capture the image, gathered
+    /// from IFD0 and the EXIF IFD.
+    pub fn camera_settings(&self) -> CameraSettings {
+        CameraSettings::from_data(self)
+    }
+
+    /// Export the entries as an object shaped like the output of
+    /// `exiftool -j -G1`.
+    ///
+    /// Keys are tag names prefixed with the exiftool group of their IFD, such
+    /// as `IFD0:Make` or `ExifIFD:ExposureTime`. Tags unknown to libexif are
+    /// named after their code, such as `ExifIFD:Exif_0xa500`. The maker note
+    /// and the location of the thumbnail are not exported.
+    #[cfg(feature = "json")]
+    pub fn to_exiftool_json(&self, mode: JsonMode) -> serde_json::Map<String, serde_json::Value> {
+        json::export(self, mode)
+    }
+
+    /// Apply an object shaped like the output of `exiftool -j -G1 -n`, or the
+    /// first object of an array of them, to the entries.
+    ///
+    /// Each key with an IFD group replaces the value of the corresponding
+    /// entry, or removes the entry if the value is `null`. Keys of other
+    /// groups, such as `File:FileSize` or `Composite:ShutterSpeed`, are
+    /// ignored, as are the thumbnail and maker note locations that libexif
+    /// manages. Keys naming an unknown tag are skipped and reported through
+    /// [`take_diagnostics()`](#method.take_diagnostics). Values must be in the
+    /// style of [`JsonMode::Numeric`](enum.JsonMode.html#variant.Numeric).
+    /// Returns [`Error::InvalidJson`](enum.Error.html#variant.InvalidJson)
+    /// with the offending key if a value cannot be converted, in which case
+    /// the data is left unchanged.
+    #[cfg(feature = "json")]
+    pub fn apply_exiftool_json(&mut self, json: &serde_json::Value) -> Result<(), super::Error> {
+        json::import(self, json)
+    }
+
+    /// Convert the entries that have an XMP equivalent to an XMP packet.
+    ///
+    /// Tags are mapped to the `tiff`, `exif` and `aux` namespaces, except for
+    /// `DateTime`, `ImageDescription`, `Artist` and `Copyright`, which map to
+    /// `xmp:ModifyDate`, `dc:description`, `dc:creator` and `dc:rights`.
+    /// Dates are written in ISO 8601 form, including the sub-seconds and the
+    /// offset from UTC if recorded, coordinates as `DDD,MM.mmmmmmk` and
+    /// rationals as `n/d`. Tags without an XMP equivalent are left out.
+    #[cfg(feature = "xmp")]
+    pub fn to_xmp(&self) -> String {
+        xmp::export(self)
+    }
+
+    /// Apply the properties of an XMP packet that have an EXIF equivalent to
+    /// the entries, as mapped by [`to_xmp()`](#method.to_xmp).
+    ///
+    /// Properties of other namespaces are ignored. Returns
+    /// [`Error::InvalidXmp`](enum.Error.html#variant.InvalidXmp), naming the
+    /// property, its value and the reason, if the packet is malformed or a
+    /// value cannot be converted, in which case the data is left unchanged.
+    #[cfg(feature = "xmp")]
+    pub fn apply_xmp(&mut self, packet: &str) -> Result<(), super::Error> {
+        xmp::import(self, packet)
+    }
+
+    /// Return the location recorded in the GPS IFD, if any.
+    pub fn gps(&self) -> Option<GpsInfo> {
+        GpsInfo::from_data(self)
+    }
+
+    /// Record a location in the GPS IFD.
+    ///
+    /// Sets `GPSVersionID` to 2.2.0.0 and replaces the tags corresponding to
+    /// the fields of `gps`. The tags of fields that are `None` are removed,
+    /// while other tags in the GPS IFD are kept. Returns
+    /// [`Error::InvalidGps`](enum.Error.html#variant.InvalidGps) if a
+    /// coordinate is out of range or the timestamp lies outside of the years
+    /// 0 to 9999, in which case the data is left unchanged.
+    pub fn set_gps(&mut self, gps: &GpsInfo) -> Result<(), super::Error> {
+        gps.write_to(self)
+    }
+
+    /// Return the content of the given IFD.
+    pub fn content<'a>(&'a self, ifd: IFD) -> Content<'a> {
+        let index: ExifIfd = ifd.into();
+        Content::from_libexif(unsafe { &*self.inner.ifd[index as usize] })
+    }
+
+    /// Return the content of the given IFD for adding or removing entries.
+    pub fn content_mut<'a>(&'a mut self, ifd: IFD) -> ContentMut<'a> {
+        let index: ExifIfd = ifd.into();
+        ContentMut::from_libexif((unsafe { &mut *self.inner.ifd[index as usize] }, self.mem))
+    }
+
+    /// Return the entry for the given tag in the given IFD, if any.
+    pub fn get<'a>(&'a self, ifd: IFD, tag: Tag) -> Option<Entry<'a>> {
+        let index: ExifIfd = ifd.into();
+        let entry = unsafe { exif_content_get_entry(self.inner.ifd[index as usize], tag.code()) };
+        if entry.is_null() {
+            None
+        } else {
+            Some(Entry::from_libexif(unsafe { &*entry }))
+        }
+    }
+
+    /// Return the entry for the given tag in the given IFD for modification,
+    /// if any.
+    pub fn get_mut<'a>(&'a mut self, ifd: IFD, tag: Tag) -> Option<EntryMut<'a>> {
+        let index: ExifIfd = ifd.into();
+        let entry = unsafe { exif_content_get_entry(self.inner.ifd[index as usize], tag.code()) };
+        if entry.is_null() {
+            None
+        } else {
+            Some(EntryMut::from_libexif((unsafe { &mut *entry }, self.mem)))
+        }
+    }
+
+    /// Search all IFDs for an entry with the given tag.
+    ///
+    /// The IFDs are searched in the order they are laid out by the EXIF
+    /// specification: the primary image, EXIF, GPS and interoperability IFDs,
+    /// followed by the thumbnail IFD.
+    pub fn find<'a>(&'a self, tag: Tag) -> Option<Entry<'a>> {
+        SPEC_ORDER.iter().find_map(|ifd| self.get(*ifd, tag))
+    }
+
+    /// Remove and return the messages libexif has reported for this data.
+    ///
+    /// libexif reports corrupt entries, unknown tags and the changes made by
+    /// [`fix()`](#method.fix), along with debugging output while loading.
+    /// The keys skipped by `apply_exiftool_json()` are reported as well.
+    /// Messages collected while loading from a reader or file include those
+    /// reported by the [`Loader`](struct.Loader.html). When the `log` feature
+    /// is enabled, each message is also passed to the `log` crate with the
+    /// target `libexif`.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.log.take()
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn report(&self, diagnostic: Diagnostic) {
+        self.log.push(diagnostic);
+    }
+
     /// Fix the EXIF data to make it compatible with the EXIF specification.
     pub fn fix(&mut self) {
         unsafe {
@@ -130,7 +657,7 @@ impl<'a> Iterator for Contents<'a> {
         if self.index < self.contents.len() {
             let content = self.contents[self.index];
             self.index += 1;
-            Some(Content::from_libexif(unsafe { mem::transmute(content) }))
+            Some(Content::from_libexif(unsafe { &*content }))
         } else {
             None
         }
@@ -147,3 +674,96 @@ impl<'a> ExactSizeIterator for Contents<'a> {
         self.contents.len()
     }
 }
+
+/// Return `true` if the bytes start with a TIFF header.
+fn is_tiff(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*")
+}
+
+/// Return the TIFF structure that libexif loads from a raw EXIF block, or
+/// `None` if libexif finds no EXIF header in it.
+///
+/// Like libexif, this skips the segments of a JPEG image that precede the
+/// APP1 segment.
+fn find_tiff(bytes: &[u8]) -> Option<&[u8]> {
+    if is_tiff(bytes) {
+        return Some(bytes);
+    }
+    let mut pos = 0;
+    if !bytes.starts_with(EXIF_HEADER) {
+        loop {
+            // any number of 0xff fill bytes may precede a marker
+            while bytes.get(pos) == Some(&0xff) {
+                pos += 1;
+            }
+            match *bytes.get(pos)? {
+                0xd8 => pos += 1,
+                // the EXIF header follows the length of the APP1 segment
+                0xe1 => {
+                    pos += 3;
+                    break;
+                }
+                0xe0..=0xef | 0xfe => {
+                    let length = bytes.get(pos + 1..pos + 3)?;
+                    pos += 1 + u16::from_be_bytes([length[0], length[1]]) as usize;
+                }
+                _ => return None,
+            }
+        }
+    }
+    let tiff = bytes.get(pos..)?.strip_prefix(&EXIF_HEADER[..])?;
+    // libexif needs the byte order mark and the offset of the first IFD
+    if tiff.len() < 8 || tiff::read_byte_order(tiff, 0).is_none() {
+        return None;
+    }
+    Some(tiff)
+}
+
+/// Return the position of the value of the MakerNote entry in the EXIF IFD of
+/// a TIFF structure.
+fn maker_note_offset(tiff: &[u8]) -> Option<usize> {
+    let byte_order = tiff::read_byte_order(tiff, 0)?;
+    let ifd0 = tiff::read_u32(tiff, 4, byte_order)? as usize;
+    let pointer = tiff::value_offset(tiff, ifd0, Tag::EXIF_IFD_POINTER.code() as u16, byte_order)?;
+    let exif_ifd = tiff::read_u32(tiff, pointer, byte_order)? as usize;
+    tiff::value_offset(tiff, exif_ifd, Tag::MAKER_NOTE.code() as u16, byte_order)
+}
+
+/// Feed the loader from the reader until it has seen the EXIF data, then
+/// construct the data.
+fn load_from<R: Read>(mut loader: Loader, mut reader: R) -> Result<Data, super::Error> {
+    let mut buffer = [0u8; 1024];
+    // the start of the stream tells why no EXIF data was found
+    let mut start = Vec::with_capacity(8);
+    loop {
+        let len = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        start.extend_from_slice(&buffer[..len.min(8 - start.len())]);
+        loader.write_all(&buffer[..len])?;
+        if !loader.needs_more() {
+            break;
+        }
+    }
+    let Some(data) = loader.load_data()? else {
+        // the formats understood by the loader
+        return Err(
+            if start.starts_with(&[0xff, 0xd8])
+                || start.starts_with(EXIF_HEADER)
+                || start.starts_with(b"FUJIFILM")
+            {
+                super::Error::NoExifFound
+            } else {
+                super::Error::UnsupportedContainer
+            },
+        );
+    };
+    if loader.needs_more() {
+        return Err(super::Error::Truncated);
+    }
+    data.log.prepend(loader.take_diagnostics());
+    Ok(data)
+}
@@ -0,0 +1,181 @@
+use crate::bits::*;
+use crate::data::Data;
+use crate::tag::Tag;
+use crate::text::{format_offset, parse_offset};
+use crate::value::Value;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Timelike};
+
+/// Format of the `DateTime`, `DateTimeOriginal` and `DateTimeDigitized` tags.
+const DATE_TIME_FORMAT: &str = "%Y:%m:%d %H:%M:%S";
+
+/// Selects one of the date and time tags.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum DateTimeKind {
+    /// When the file was last changed, from `DateTime`, `OffsetTime` and
+    /// `SubSecTime`.
+    Modified,
+    /// When the image was captured, from `DateTimeOriginal`,
+    /// `OffsetTimeOriginal` and `SubSecTimeOriginal`.
+    Original,
+    /// When the image was stored as digital data, from `DateTimeDigitized`,
+    /// `OffsetTimeDigitized` and `SubSecTimeDigitized`.
+    Digitized,
+}
+
+impl DateTimeKind {
+    /// Return the IFD and tag of the date and time, and the tags of the
+    /// offset and sub-seconds, which are stored in the EXIF IFD.
+    fn tags(self) -> (IFD, Tag, Tag, Tag) {
+        match self {
+            DateTimeKind::Modified => (
+                IFD::Image,
+                Tag::DATE_TIME,
+                Tag::OFFSET_TIME,
+                Tag::SUB_SEC_TIME,
+            ),
+            DateTimeKind::Original => (
+                IFD::EXIF,
+                Tag::DATE_TIME_ORIGINAL,
+                Tag::OFFSET_TIME_ORIGINAL,
+                Tag::SUB_SEC_TIME_ORIGINAL,
+            ),
+            DateTimeKind::Digitized => (
+                IFD::EXIF,
+                Tag::DATE_TIME_DIGITIZED,
+                Tag::OFFSET_TIME_DIGITIZED,
+                Tag::SUB_SEC_TIME_DIGITIZED,
+            ),
+        }
+    }
+}
+
+/// A date and time recorded in the EXIF data.
+///
+/// EXIF records local time. The offset from UTC is only known if the
+/// corresponding `OffsetTime` tag is present, otherwise the time is naive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ExifDateTime {
+    /// Local time without a known offset from UTC.
+    Naive(NaiveDateTime),
+    /// Local time with its offset from UTC.
+    Offset(DateTime<FixedOffset>),
+}
+
+impl ExifDateTime {
+    /// Return the local date and time.
+    pub fn naive_local(&self) -> NaiveDateTime {
+        match self {
+            ExifDateTime::Naive(naive) => *naive,
+            ExifDateTime::Offset(date_time) => date_time.naive_local(),
+        }
+    }
+
+    /// Return the offset from UTC, if known.
+    pub fn offset(&self) -> Option<FixedOffset> {
+        match self {
+            ExifDateTime::Naive(_) => None,
+            ExifDateTime::Offset(date_time) => Some(*date_time.offset()),
+        }
+    }
+}
+
+impl From<NaiveDateTime> for ExifDateTime {
+    fn from(naive: NaiveDateTime) -> Self {
+        ExifDateTime::Naive(naive)
+    }
+}
+
+impl From<DateTime<FixedOffset>> for ExifDateTime {
+    fn from(date_time: DateTime<FixedOffset>) -> Self {
+        ExifDateTime::Offset(date_time)
+    }
+}
+
+impl ExifDateTime {
+    /// Read the date and time of the given kind, returning `None` if the tag
+    /// is missing, blank or malformed. A malformed offset or sub-second tag
+    /// is ignored.
+    pub(crate) fn from_data(data: &Data, kind: DateTimeKind) -> Option<ExifDateTime> {
+        let byte_order = data.byte_order().ok()?;
+        let text = |ifd: IFD, tag: Tag| match data.get(ifd, tag)?.value(byte_order).ok()? {
+            Value::Text(text) => Some(text),
+            _ => None,
+        };
+        let (ifd, date_time_tag, offset_tag, sub_sec_tag) = kind.tags();
+        let mut naive = NaiveDateTime::parse_from_str(
+            text(ifd, date_time_tag)?.trim_end_matches([' ', '\0']),
+            DATE_TIME_FORMAT,
+        )
+        .ok()?;
+        if let Some(nanos) = text(IFD::EXIF, sub_sec_tag).and_then(|text| parse_sub_sec(&text)) {
+            naive = naive.with_nanosecond(nanos)?;
+        }
+        let offset = text(IFD::EXIF, offset_tag)
+            .and_then(|text| parse_offset(&text))
+            .and_then(|minutes| FixedOffset::east_opt(minutes * 60));
+        match offset {
+            Some(offset) => Some(ExifDateTime::Offset(
+                naive.and_local_timezone(offset).single()?,
+            )),
+            None => Some(ExifDateTime::Naive(naive)),
+        }
+    }
+
+    /// Write the date and time, replacing or removing the offset and
+    /// sub-second tags as needed.
+    pub(crate) fn write_to(&self, data: &mut Data, kind: DateTimeKind) -> Result<(), super::Error> {
+        let (ifd, date_time_tag, offset_tag, sub_sec_tag) = kind.tags();
+        let naive = self.naive_local();
+        data.content_mut(ifd).insert_value(
+            date_time_tag,
+            Value::Text(naive.format(DATE_TIME_FORMAT).to_string()),
+        )?;
+        let mut content = data.content_mut(IFD::EXIF);
+        match format_sub_sec(naive.nanosecond()) {
+            Some(sub_sec) => {
+                content.insert_value(sub_sec_tag, Value::Text(sub_sec))?;
+            }
+            None => {
+                content.remove(sub_sec_tag);
+            }
+        }
+        match self.offset() {
+            Some(offset) => {
+                content.insert_value(
+                    offset_tag,
+                    Value::Text(format_offset(offset.local_minus_utc() / 60)),
+                )?;
+            }
+            None => {
+                content.remove(offset_tag);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse the digits of a `SubSecTime` tag into nanoseconds.
+fn parse_sub_sec(text: &str) -> Option<u32> {
+    let digits = text.trim_start().bytes().take_while(u8::is_ascii_digit);
+    let mut nanos = 0;
+    let mut count = 0;
+    for digit in digits.take(9) {
+        nanos = nanos * 10 + u32::from(digit - b'0');
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+    Some(nanos * 10u32.pow(9 - count))
+}
+
+/// Format nanoseconds as the digits of a `SubSecTime` tag, without trailing
+/// zeros. Returns `None` for whole seconds.
+fn format_sub_sec(nanos: u32) -> Option<String> {
+    if nanos == 0 {
+        return None;
+    }
+    // leap seconds are represented by nanoseconds beyond one second
+    let digits = format!("{:09}", nanos % 1_000_000_000);
+    Some(digits.trim_end_matches('0').to_string()).filter(|digits| !digits.is_empty())
+}
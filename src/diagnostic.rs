@@ -0,0 +1,228 @@
+use libc::{c_char, c_int, c_void};
+use libexif_sys::*;
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+use std::ptr;
+
+/// Category of a diagnostic message reported by libexif.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum LogCode {
+    /// Informational message.
+    None,
+    /// Debugging output, such as the progress of loading the data.
+    Debug,
+    /// libexif ran out of memory.
+    NoMemory,
+    /// The EXIF data is corrupt, or was fixed up to follow the specification.
+    CorruptData,
+}
+
+impl From<ExifLogCode> for LogCode {
+    fn from(code: ExifLogCode) -> Self {
+        match code {
+            ExifLogCode_EXIF_LOG_CODE_DEBUG => LogCode::Debug,
+            ExifLogCode_EXIF_LOG_CODE_NO_MEMORY => LogCode::NoMemory,
+            ExifLogCode_EXIF_LOG_CODE_CORRUPT_DATA => LogCode::CorruptData,
+            _ => LogCode::None,
+        }
+    }
+}
+
+/// A message reported by libexif, or by this crate, while processing EXIF
+/// data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    code: LogCode,
+    domain: String,
+    message: String,
+}
+
+impl Diagnostic {
+    #[cfg(feature = "json")]
+    pub(crate) fn new(code: LogCode, domain: &str, message: String) -> Diagnostic {
+        Diagnostic {
+            code,
+            domain: domain.to_string(),
+            message,
+        }
+    }
+
+    /// The category of the message.
+    pub fn code(&self) -> LogCode {
+        self.code
+    }
+
+    /// The part of libexif that reported the message, such as `"ExifData"`,
+    /// or `"ExifJson"` for the keys skipped while applying exiftool-style
+    /// JSON.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// The formatted message.
+    ///
+    /// The arguments of libexif's messages are only expanded on x86 and
+    /// x86-64. On other platforms the message is libexif's format string,
+    /// such as `Tag 0x%04x is not recorded in IFD '%s'.`.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}: {}", self.domain, self.message)
+    }
+}
+
+/// Names the type of the `va_list` argument of libexif's log callback, which
+/// the bindings spell out differently on every platform.
+trait LogFuncArgs {
+    type VaList;
+}
+
+impl<V> LogFuncArgs
+    for Option<
+        unsafe extern "C" fn(
+            *mut ExifLog,
+            ExifLogCode,
+            *const c_char,
+            *const c_char,
+            V,
+            *mut c_void,
+        ),
+    >
+{
+    type VaList = V;
+}
+
+/// A `va_list` as received by the log callback.
+type VaList = <ExifLogFunc as LogFuncArgs>::VaList;
+
+// Rust has no stable way to forward a `va_list`. On x86 and x86-64, the
+// bindings' type is passed exactly like C's `va_list`, so it can be handed to
+// vsnprintf() as is.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+extern "C" {
+    fn vsnprintf(buffer: *mut c_char, size: usize, format: *const c_char, args: VaList) -> c_int;
+}
+
+/// An `ExifLog` that collects the messages reported by libexif.
+pub(crate) struct Log {
+    inner: *mut ExifLog,
+    // boxed so that the address handed to libexif stays put
+    sink: Box<RefCell<Vec<Diagnostic>>>,
+}
+
+impl Drop for Log {
+    fn drop(&mut self) {
+        unsafe {
+            // libexif objects may hold on to the log beyond our lifetime
+            exif_log_set_func(self.inner, None, ptr::null_mut());
+            exif_log_unref(self.inner);
+        }
+    }
+}
+
+impl Log {
+    pub fn new() -> Log {
+        let inner = unsafe { exif_log_new() };
+        assert!(!inner.is_null());
+        let sink = Box::new(RefCell::new(Vec::new()));
+        unsafe {
+            exif_log_set_func(
+                inner,
+                Some(log_func),
+                &*sink as *const RefCell<Vec<Diagnostic>> as *mut c_void,
+            );
+        }
+        Log { inner, sink }
+    }
+
+    pub fn as_ptr(&self) -> *mut ExifLog {
+        self.inner
+    }
+
+    /// Insert messages collected elsewhere before the messages collected so
+    /// far.
+    pub fn prepend(&self, diagnostics: Vec<Diagnostic>) {
+        self.sink.borrow_mut().splice(0..0, diagnostics);
+    }
+
+    /// Add a message reported by this crate.
+    #[cfg(feature = "json")]
+    pub fn push(&self, diagnostic: Diagnostic) {
+        record(&self.sink, diagnostic);
+    }
+
+    /// Remove and return the messages collected so far.
+    pub fn take(&self) -> Vec<Diagnostic> {
+        mem::take(&mut *self.sink.borrow_mut())
+    }
+}
+
+unsafe extern "C" fn log_func(
+    _log: *mut ExifLog,
+    code: ExifLogCode,
+    domain: *const c_char,
+    format: *const c_char,
+    args: VaList,
+    data: *mut c_void,
+) {
+    if data.is_null() || format.is_null() {
+        return;
+    }
+    let Some(message) = format_message(format, args) else {
+        return;
+    };
+    let diagnostic = Diagnostic {
+        code: code.into(),
+        domain: if domain.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(domain).to_string_lossy().into_owned()
+        },
+        message,
+    };
+    record(&*(data as *const RefCell<Vec<Diagnostic>>), diagnostic);
+}
+
+/// Expand the arguments of a message reported by libexif.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+unsafe fn format_message(format: *const c_char, args: VaList) -> Option<String> {
+    let mut buffer = [0 as c_char; 1024];
+    if vsnprintf(buffer.as_mut_ptr(), buffer.len(), format, args) < 0 {
+        return None;
+    }
+    Some(
+        CStr::from_ptr(buffer.as_ptr())
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+/// Return the format string of a message reported by libexif, as its
+/// arguments cannot be expanded safely on this platform.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+unsafe fn format_message(format: *const c_char, _args: VaList) -> Option<String> {
+    Some(CStr::from_ptr(format).to_string_lossy().into_owned())
+}
+
+fn record(sink: &RefCell<Vec<Diagnostic>>, diagnostic: Diagnostic) {
+    #[cfg(feature = "log")]
+    {
+        let level = match diagnostic.code {
+            LogCode::None => log::Level::Info,
+            LogCode::Debug => log::Level::Debug,
+            LogCode::NoMemory => log::Level::Error,
+            LogCode::CorruptData => log::Level::Warn,
+        };
+        log::log!(target: "libexif", level, "{}", diagnostic);
+    }
+    // a message reported while the diagnostics are being taken is dropped
+    if let Ok(mut diagnostics) = sink.try_borrow_mut() {
+        diagnostics.push(diagnostic);
+    }
+}
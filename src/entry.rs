@@ -4,49 +4,142 @@
 use crate::bits::*;
 use crate::internal::*;
 use crate::tag::Tag;
-use crate::value::Value;
-use libc::{self, c_char, c_uint};
+use crate::value::{Rational, SizeMismatch, Value};
+use libc::{c_char, c_uint, c_ulong, c_void};
 use libexif_sys::*;
-use std::ffi::CString;
-use std::ffi::IntoStringError;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr::{self, NonNull};
 use std::slice;
+use std::sync::Mutex;
+
+/// Tags that the EXIF specification allows to be recorded as either SHORT or
+/// LONG values.
+const SHORT_OR_LONG_TAGS: [Tag; 7] = [
+    Tag::IMAGE_WIDTH,
+    Tag::IMAGE_LENGTH,
+    Tag::STRIP_OFFSETS,
+    Tag::ROWS_PER_STRIP,
+    Tag::STRIP_BYTE_COUNTS,
+    Tag::PIXEL_X_DIMENSION,
+    Tag::PIXEL_Y_DIMENSION,
+];
+
+/// Tags that libexif renders with units, along with the number of decimals it
+/// renders their values with.
+const UNIT_TAGS: [(Tag, usize); 9] = [
+    (Tag::EXPOSURE_TIME, 0),
+    (Tag::FNUMBER, 1),
+    (Tag::SHUTTER_SPEED_VALUE, 2),
+    (Tag::APERTURE_VALUE, 2),
+    (Tag::BRIGHTNESS_VALUE, 2),
+    (Tag::EXPOSURE_BIAS_VALUE, 2),
+    (Tag::MAX_APERTURE_VALUE, 2),
+    (Tag::SUBJECT_DISTANCE, 1),
+    (Tag::FOCAL_LENGTH, 1),
+];
+
+/// Upper bound on the buffer used to render an entry as text.
+const MAX_TEXT_BUFFER: usize = 1 << 20;
 
 /// Data found in a single EXIF tag.
 pub struct Entry<'a> {
-    inner: &'a mut ExifEntry,
+    inner: NonNull<ExifEntry>,
+    marker: PhantomData<&'a ExifEntry>,
+}
+
+impl<'a> FromLibExif<&'a ExifEntry> for Entry<'a> {
+    fn from_libexif(entry: &'a ExifEntry) -> Self {
+        Entry {
+            inner: NonNull::from(entry),
+            marker: PhantomData,
+        }
+    }
 }
 
-impl<'a> FromLibExif<&'a mut ExifEntry> for Entry<'a> {
-    fn from_libexif(entry: &'a mut ExifEntry) -> Self {
-        Entry { inner: entry }
+/// Data found in a single EXIF tag, borrowed for modification.
+///
+/// Dereferences to [`Entry`](struct.Entry.html) for reading the data. It is
+/// returned by [`Data::get_mut()`](struct.Data.html#method.get_mut) and the
+/// methods of [`ContentMut`](struct.ContentMut.html).
+pub struct EntryMut<'a> {
+    entry: Entry<'a>,
+    /// The allocator of the data containing the entry.
+    mem: *mut ExifMem,
+    marker: PhantomData<&'a mut ExifEntry>,
+}
+
+impl<'a> FromLibExif<(&'a mut ExifEntry, *mut ExifMem)> for EntryMut<'a> {
+    fn from_libexif((entry, mem): (&'a mut ExifEntry, *mut ExifMem)) -> Self {
+        EntryMut {
+            entry: Entry {
+                inner: NonNull::from(entry),
+                marker: PhantomData,
+            },
+            mem,
+            marker: PhantomData,
+        }
     }
 }
 
 impl<'a> Entry<'a> {
     /// EXIF tag for the entry.
     pub fn tag(&self) -> Tag {
-        Tag::from(self.inner.tag)
+        Tag::from(self.raw().tag)
     }
 
     /// Type of data contained in the entry.
     pub fn data_type(&self) -> Result<DataType, super::Error> {
-        DataType::try_from(self.inner.format)
+        DataType::try_from(self.raw().format)
     }
 
     /// Number of data elements in the entry.
     pub fn components(&self) -> usize {
-        self.inner.components as usize
+        self.raw().components as usize
     }
 
     /// Return the raw binary data for the entry's value.
     pub fn raw_data(&self) -> &[u8] {
-        unsafe { slice::from_raw_parts(self.inner.data, self.inner.size as usize) }
+        if self.raw().data.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.raw().data, self.raw().size as usize) }
+        }
     }
 
     /// Returns an interpreted value of the entry's data.
+    ///
+    /// Returns [`Error::InvalidEntry`](enum.Error.html#variant.InvalidEntry)
+    /// if the entry's data type is unknown, and
+    /// [`Error::EntrySizeMismatch`](enum.Error.html#variant.EntrySizeMismatch)
+    /// if the size of its data does not match its data type and number of
+    /// components. Use [`value_lenient()`](#method.value_lenient) to decode
+    /// such entries anyway.
     pub fn value(&self, byte_order: ByteOrder) -> Result<Value, super::Error> {
-        let data_type = self.data_type()?;
-        Ok(Value::extract(
+        let data_type = self.data_type().map_err(|_| self.invalid())?;
+        Value::extract(self.raw_data(), data_type, self.components(), byte_order).map_err(
+            |mismatch| super::Error::EntrySizeMismatch {
+                ifd: self.public_ifd(),
+                tag: self.tag(),
+                expected_size: mismatch.expected_size,
+                actual_size: mismatch.actual_size,
+            },
+        )
+    }
+
+    /// Returns an interpreted value of the entry's data, tolerating data whose
+    /// size does not match the entry's data type and number of components.
+    ///
+    /// As many whole components as are present in the data are decoded, and
+    /// the discrepancy, if any, is returned along with the value. An error is
+    /// returned only if the entry's data type is unknown.
+    pub fn value_lenient(
+        &self,
+        byte_order: ByteOrder,
+    ) -> Result<(Value, Option<SizeMismatch>), super::Error> {
+        let data_type = self.data_type().map_err(|_| self.invalid())?;
+        Ok(Value::extract_lenient(
             self.raw_data(),
             data_type,
             self.components(),
@@ -54,18 +147,250 @@ impl<'a> Entry<'a> {
         ))
     }
 
-    /// Returns a textual representation of the entry's data.
-    pub fn text_value(&self) -> Result<String, IntoStringError> {
-        let mut buffer = Vec::<u8>::with_capacity(256);
-        let cstring = unsafe {
-            let len = libc::strlen(exif_entry_get_value(
-                self.inner as *const _ as *mut _,
-                buffer.as_mut_ptr() as *mut c_char,
-                buffer.capacity() as c_uint,
-            ));
-            buffer.set_len(len);
-            CString::from_vec_unchecked(buffer)
-        };
-        cstring.into_string()
+    pub(crate) fn as_ptr(&self) -> *mut ExifEntry {
+        self.inner.as_ptr()
+    }
+
+    fn raw(&self) -> &ExifEntry {
+        unsafe { self.inner.as_ref() }
+    }
+
+    /// Return the IFD containing the entry.
+    pub(crate) fn ifd(&self) -> ExifIfd {
+        if self.raw().parent.is_null() {
+            ExifIfd_EXIF_IFD_0
+        } else {
+            unsafe { exif_content_get_ifd(self.raw().parent) }
+        }
+    }
+
+    /// Return the IFD containing the entry, or the primary image IFD if
+    /// libexif does not know it.
+    fn public_ifd(&self) -> IFD {
+        IFD::try_from(self.ifd()).unwrap_or(IFD::Image)
+    }
+
+    /// Construct the error describing this entry as invalid.
+    fn invalid(&self) -> super::Error {
+        super::Error::InvalidEntry {
+            ifd: self.public_ifd(),
+            tag: self.tag(),
+        }
+    }
+
+    /// Returns a human-readable representation of the entry's data, as
+    /// formatted by libexif.
+    ///
+    /// Equivalent to [`format_value(TextFormat::Readable)`](#method.format_value).
+    pub fn text_value(&self) -> Result<String, super::Error> {
+        self.format_value(TextFormat::Readable)
+    }
+
+    /// Returns a textual representation of the entry's data in the given
+    /// format.
+    ///
+    /// Text formatted by libexif is limited to 1 MiB and truncated beyond
+    /// that, which only affects entries with hundreds of kilobytes of data.
+    pub fn format_value(&self, format: TextFormat) -> Result<String, super::Error> {
+        match format {
+            TextFormat::Raw => Ok(self.value(self.byte_order()?)?.to_string()),
+            TextFormat::Readable => self.readable_value(),
+            TextFormat::ReadableWithoutUnits => {
+                let Some(&(_, decimals)) = UNIT_TAGS.iter().find(|(tag, _)| *tag == self.tag())
+                else {
+                    return self.readable_value();
+                };
+                let value = self.value(self.byte_order()?)?;
+                let number = match &value {
+                    Value::URational(v) => v.first().and_then(Rational::to_f64),
+                    Value::IRational(v) => v.first().and_then(Rational::to_f64),
+                    _ => None,
+                };
+                // the text produced by libexif depends on the locale, so the
+                // number is formatted here with the precision libexif uses
+                Ok(match number {
+                    Some(seconds)
+                        if self.tag() == Tag::EXPOSURE_TIME && seconds > 0.0 && seconds < 1.0 =>
+                    {
+                        format!("1/{:.0}", 1.0 / seconds)
+                    }
+                    Some(number) => format!("{:.*}", decimals, number),
+                    None => value.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Render the entry with libexif.
+    fn readable_value(&self) -> Result<String, super::Error> {
+        // libexif renders undefined data as hex, which takes up to three
+        // characters per byte
+        let size = (self.raw_data().len() * 3 + 64).max(256);
+        let buffer = render_text(size, |buffer, len| unsafe {
+            exif_entry_get_value(self.inner.as_ptr(), buffer, len)
+        })
+        .unwrap_or_default();
+        String::from_utf8(buffer).map_err(|err| super::Error::InvalidText(err.utf8_error()))
+    }
+
+    /// Return the byte order of the data containing the entry.
+    pub(crate) fn byte_order(&self) -> Result<ByteOrder, super::Error> {
+        let content = self.raw().parent;
+        if content.is_null() || unsafe { (*content).parent.is_null() } {
+            return Err(self.invalid());
+        }
+        ByteOrder::try_from(unsafe { exif_data_get_byte_order((*content).parent) })
+    }
+}
+
+impl<'a> EntryMut<'a> {
+    /// Replace the entry's data with the given value.
+    ///
+    /// The value is encoded using the given byte order, which should be the
+    /// byte order of the [`Data`](struct.Data.html) that contains the entry.
+    /// The entry's data type and number of components are updated to match
+    /// the value. An error is returned if the value's data type is not
+    /// allowed for the tag by the EXIF specification.
+    pub fn set_value(&mut self, value: Value, byte_order: ByteOrder) -> Result<(), super::Error> {
+        let data_type = value.data_type();
+        if !allows_data_type(self.tag(), self.ifd(), data_type) {
+            return Err(super::Error::IllegalValueType);
+        }
+        let bytes = value.encode(byte_order);
+        let inner = unsafe { &mut *self.entry.inner.as_ptr() };
+        let mem = self.mem;
+        unsafe {
+            if bytes.is_empty() {
+                exif_mem_free(mem, inner.data as *mut c_void);
+                inner.data = ptr::null_mut();
+            } else {
+                if bytes.len() != inner.size as usize || inner.data.is_null() {
+                    let buffer =
+                        exif_mem_realloc(mem, inner.data as *mut c_void, bytes.len() as ExifLong);
+                    if buffer.is_null() {
+                        return Err(super::Error::OutOfMemory);
+                    }
+                    inner.data = buffer as *mut u8;
+                }
+                ptr::copy_nonoverlapping(bytes.as_ptr(), inner.data, bytes.len());
+            }
+        }
+        inner.size = bytes.len() as c_uint;
+        inner.format = data_type.into();
+        inner.components = value.components() as c_ulong;
+        Ok(())
+    }
+}
+
+impl<'a> Deref for EntryMut<'a> {
+    type Target = Entry<'a>;
+
+    fn deref(&self) -> &Entry<'a> {
+        &self.entry
+    }
+}
+
+/// Format of the text produced by
+/// [`Entry::format_value()`](struct.Entry.html#method.format_value).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TextFormat {
+    /// The decoded value, such as `1/250` or `2, 0, 0` or `30 32 33 30`, as
+    /// produced by the `Display` implementation of
+    /// [`Value`](enum.Value.html).
+    Raw,
+    /// The value as formatted by libexif, including units, such as
+    /// `1/250 sec.` or `f/2.8`.
+    Readable,
+    /// The value as formatted by libexif, except that exposure, aperture,
+    /// brightness, distance and focal length values are plain numbers without
+    /// units, such as `1/250` or `2.8`.
+    ReadableWithoutUnits,
+}
+
+/// Render text with a libexif function that writes into a buffer, starting
+/// with a buffer of `size` bytes and growing it until the text fits.
+///
+/// Returns `None` if the function produced no text.
+pub(crate) fn render_text<F>(size: usize, mut render: F) -> Option<Vec<u8>>
+where
+    F: FnMut(*mut c_char, c_uint) -> *const c_char,
+{
+    let mut buffer = vec![0u8; size];
+    loop {
+        if render(buffer.as_mut_ptr() as *mut c_char, buffer.len() as c_uint).is_null() {
+            return None;
+        }
+        let len = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+        // libexif leaves room for the terminating null byte, so text that
+        // fills the buffer may have been truncated
+        if len + 1 < buffer.len() || buffer.len() >= MAX_TEXT_BUFFER {
+            buffer.truncate(len);
+            return Some(buffer);
+        }
+        buffer = vec![0u8; buffer.len() * 2];
+    }
+}
+
+/// Return whether the EXIF specification allows values of the given data type
+/// for the tag when found in the given IFD.
+///
+/// Tags without a known data type accept values of any type.
+pub(crate) fn allows_data_type(tag: Tag, ifd: ExifIfd, data_type: DataType) -> bool {
+    match spec_data_type(tag.code(), ifd) {
+        Some(expected) => {
+            let interchangeable = SHORT_OR_LONG_TAGS.contains(&tag)
+                && matches!(data_type, DataType::U16 | DataType::U32)
+                && matches!(expected, DataType::U16 | DataType::U32);
+            data_type == expected || interchangeable
+        }
+        None => true,
+    }
+}
+
+/// Data types prescribed for tags, as determined by `spec_data_type()`.
+static SPEC_DATA_TYPES: Mutex<BTreeMap<(ExifTag, ExifIfd), Option<DataType>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Return the data type prescribed by the EXIF specification for the tag when
+/// found in the given IFD, or `None` if libexif does not know it.
+///
+/// libexif has no direct way to query this, so a scratch entry is initialized
+/// with the tag's default value and its format is examined. libexif leaves
+/// tags it has no default for as undefined data without components, which
+/// says nothing about their type. The result is cached for each tag and IFD.
+pub(crate) fn spec_data_type(tag: ExifTag, ifd: ExifIfd) -> Option<DataType> {
+    if ifd >= ExifIfd_EXIF_IFD_COUNT {
+        return None;
+    }
+    let mut cache = SPEC_DATA_TYPES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *cache
+        .entry((tag, ifd))
+        .or_insert_with(|| initialized_data_type(tag, ifd))
+}
+
+fn initialized_data_type(tag: ExifTag, ifd: ExifIfd) -> Option<DataType> {
+    unsafe {
+        let data = exif_data_new();
+        if data.is_null() {
+            return None;
+        }
+        let entry = exif_entry_new();
+        if entry.is_null() {
+            exif_data_unref(data);
+            return None;
+        }
+        // the entry must belong to a content for exif_entry_initialize() to work
+        exif_content_add_entry((*data).ifd[ifd as usize], entry);
+        exif_entry_initialize(entry, tag);
+        let format = (*entry).format;
+        let components = (*entry).components;
+        exif_entry_unref(entry);
+        exif_data_unref(data);
+        match DataType::try_from(format) {
+            Ok(DataType::Undefined) if components == 0 => None,
+            data_type => data_type.ok(),
+        }
     }
 }
@@ -0,0 +1,292 @@
+use crate::bits::*;
+use crate::data::Data;
+use crate::tag::Tag;
+use crate::value::{Rational, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Version of the GPS tags written by [`Data::set_gps()`](struct.Data.html#method.set_gps).
+const GPS_VERSION: [u8; 4] = [2, 2, 0, 0];
+
+/// Denominator used for the seconds of coordinates, which is precise to
+/// about 3 mm.
+const SECONDS_DENOMINATOR: u32 = 10000;
+
+/// Denominator used for the altitude, in meters.
+const ALTITUDE_DENOMINATOR: u32 = 1000;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Location recorded in the GPS IFD.
+///
+/// Coordinates are expressed in decimal degrees, with positive values north
+/// of the equator and east of the prime meridian. Fields are `None` if the
+/// corresponding tags are missing or invalid.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GpsInfo {
+    /// Latitude in degrees, from `GPSLatitude` and `GPSLatitudeRef`.
+    pub latitude: Option<f64>,
+    /// Longitude in degrees, from `GPSLongitude` and `GPSLongitudeRef`.
+    pub longitude: Option<f64>,
+    /// Altitude in meters, negative below sea level, from `GPSAltitude` and
+    /// `GPSAltitudeRef`.
+    pub altitude: Option<f64>,
+    /// Time of the GPS fix in UTC, from `GPSDateStamp` and `GPSTimeStamp`.
+    pub timestamp: Option<SystemTime>,
+    /// Geodetic survey data used by the receiver, such as `"WGS-84"`, from
+    /// `GPSMapDatum`.
+    pub map_datum: Option<String>,
+}
+
+impl GpsInfo {
+    /// Read the location from the GPS IFD, returning `None` if it holds none
+    /// of the supported tags.
+    pub(crate) fn from_data(data: &Data) -> Option<GpsInfo> {
+        let byte_order = data.byte_order().ok()?;
+        let value = |tag: Tag| data.get(IFD::GPS, tag)?.value(byte_order).ok();
+        let info = GpsInfo {
+            latitude: read_coordinate(value(Tag::GPS_LATITUDE), value(Tag::GPS_LATITUDE_REF), 'S'),
+            longitude: read_coordinate(
+                value(Tag::GPS_LONGITUDE),
+                value(Tag::GPS_LONGITUDE_REF),
+                'W',
+            ),
+            altitude: read_altitude(value(Tag::GPS_ALTITUDE), value(Tag::GPS_ALTITUDE_REF)),
+            timestamp: read_timestamp(value(Tag::GPS_DATE_STAMP), value(Tag::GPS_TIME_STAMP)),
+            map_datum: match value(Tag::GPS_MAP_DATUM) {
+                Some(Value::Text(text)) if !text.is_empty() => Some(text),
+                _ => None,
+            },
+        };
+        if info == GpsInfo::default() {
+            None
+        } else {
+            Some(info)
+        }
+    }
+
+    /// Write the location to the GPS IFD, removing the tags of fields that
+    /// are `None`.
+    pub(crate) fn write_to(&self, data: &mut Data) -> Result<(), super::Error> {
+        // validate everything before modifying the data
+        let latitude = self
+            .latitude
+            .map(|latitude| write_coordinate(latitude, 90.0, ["N", "S"]))
+            .transpose()?;
+        let longitude = self
+            .longitude
+            .map(|longitude| write_coordinate(longitude, 180.0, ["E", "W"]))
+            .transpose()?;
+        let altitude = self.altitude.map(write_altitude).transpose()?;
+        let timestamp = self.timestamp.map(write_timestamp).transpose()?;
+
+        let mut content = data.content_mut(IFD::GPS);
+        content.insert_value(Tag::GPS_VERSION_ID, Value::U8(GPS_VERSION.to_vec()))?;
+        let fields = [
+            (Tag::GPS_LATITUDE_REF, Tag::GPS_LATITUDE, latitude),
+            (Tag::GPS_LONGITUDE_REF, Tag::GPS_LONGITUDE, longitude),
+            (Tag::GPS_ALTITUDE_REF, Tag::GPS_ALTITUDE, altitude),
+            (Tag::GPS_DATE_STAMP, Tag::GPS_TIME_STAMP, timestamp),
+        ];
+        for (first_tag, second_tag, values) in fields {
+            match values {
+                Some((first, second)) => {
+                    content.insert_value(first_tag, first)?;
+                    content.insert_value(second_tag, second)?;
+                }
+                None => {
+                    content.remove(first_tag);
+                    content.remove(second_tag);
+                }
+            }
+        }
+        match &self.map_datum {
+            Some(map_datum) => {
+                content.insert_value(Tag::GPS_MAP_DATUM, Value::Text(map_datum.clone()))?;
+            }
+            None => {
+                content.remove(Tag::GPS_MAP_DATUM);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Convert degrees, minutes and seconds to decimal degrees. The reference is
+/// negative if it starts with `negative`.
+pub(crate) fn read_coordinate(
+    value: Option<Value>,
+    reference: Option<Value>,
+    negative: char,
+) -> Option<f64> {
+    let Some(Value::URational(parts)) = value else {
+        return None;
+    };
+    let mut degrees = 0.0;
+    let mut unit = 1.0;
+    for part in parts.iter().take(3) {
+        degrees += part.to_f64()? / unit;
+        unit *= 60.0;
+    }
+    match reference {
+        Some(Value::Text(text)) if text.starts_with(negative) => Some(-degrees),
+        _ => Some(degrees),
+    }
+}
+
+/// Convert decimal degrees to the reference and the degrees, minutes and
+/// seconds of a coordinate.
+pub(crate) fn write_coordinate(
+    degrees: f64,
+    max: f64,
+    references: [&str; 2],
+) -> Result<(Value, Value), super::Error> {
+    if !degrees.is_finite() || degrees.abs() > max {
+        return Err(super::Error::InvalidGps);
+    }
+    // round once, so that the seconds never round up to a full minute
+    let total = (degrees.abs() * 3600.0 * SECONDS_DENOMINATOR as f64).round() as u64;
+    let per_minute = 60 * SECONDS_DENOMINATOR as u64;
+    let per_degree = 60 * per_minute;
+    let reference = references[usize::from(degrees < 0.0)];
+    let dms = vec![
+        Rational((total / per_degree) as u32, 1),
+        Rational((total % per_degree / per_minute) as u32, 1),
+        Rational((total % per_minute) as u32, SECONDS_DENOMINATOR),
+    ];
+    Ok((Value::Text(reference.to_string()), Value::URational(dms)))
+}
+
+fn read_altitude(value: Option<Value>, reference: Option<Value>) -> Option<f64> {
+    let Some(Value::URational(parts)) = value else {
+        return None;
+    };
+    let altitude = parts.first()?.to_f64()?;
+    match reference {
+        // 1 means below sea level
+        Some(Value::U8(reference)) if reference.first() == Some(&1) => Some(-altitude),
+        _ => Some(altitude),
+    }
+}
+
+fn write_altitude(altitude: f64) -> Result<(Value, Value), super::Error> {
+    let scaled = (altitude.abs() * ALTITUDE_DENOMINATOR as f64).round();
+    if !scaled.is_finite() || scaled > u32::MAX as f64 {
+        return Err(super::Error::InvalidGps);
+    }
+    Ok((
+        Value::U8(vec![u8::from(altitude < 0.0)]),
+        Value::URational(vec![Rational(scaled as u32, ALTITUDE_DENOMINATOR)]),
+    ))
+}
+
+/// Combine the `YYYY:MM:DD` date stamp and the hours, minutes and seconds of
+/// the time stamp into a point in time.
+fn read_timestamp(date: Option<Value>, time: Option<Value>) -> Option<SystemTime> {
+    let (Some(Value::Text(date)), Some(Value::URational(time))) = (date, time) else {
+        return None;
+    };
+    let mut fields = date
+        .trim()
+        .splitn(3, ':')
+        .map(|field| field.parse::<i64>().ok());
+    let (year, month, day) = (fields.next()??, fields.next()??, fields.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || time.len() < 3 {
+        return None;
+    }
+    let seconds = time[0].to_f64()? * 3600.0 + time[1].to_f64()? * 60.0 + time[2].to_f64()?;
+    if !(0.0..SECONDS_PER_DAY as f64).contains(&seconds) {
+        return None;
+    }
+    // whole seconds and nanoseconds, as the seconds may be fractional
+    let whole = seconds.floor();
+    let nanos = ((seconds - whole) * 1e9).round() as u32;
+    system_time_from_civil(year, month, day, whole as i64, nanos)
+}
+
+pub(crate) fn write_timestamp(timestamp: SystemTime) -> Result<(Value, Value), super::Error> {
+    // split into whole seconds relative to the epoch and milliseconds
+    let (seconds, millis) = match timestamp.duration_since(UNIX_EPOCH) {
+        Ok(duration) => (duration.as_secs() as i64, duration.subsec_millis()),
+        Err(err) => {
+            let duration = err.duration();
+            let millis = duration.subsec_millis();
+            let seconds = -(duration.as_secs() as i64);
+            if millis == 0 {
+                (seconds, 0)
+            } else {
+                (seconds - 1, 1000 - millis)
+            }
+        }
+    };
+    let days = seconds.div_euclid(SECONDS_PER_DAY);
+    let time_of_day = seconds.rem_euclid(SECONDS_PER_DAY) as u32;
+    let (year, month, day) = civil_from_days(days);
+    if !(0..=9999).contains(&year) {
+        return Err(super::Error::InvalidGps);
+    }
+    let seconds = if millis == 0 {
+        Rational(time_of_day % 60, 1)
+    } else {
+        Rational(time_of_day % 60 * 1000 + millis, 1000)
+    };
+    let time = vec![
+        Rational(time_of_day / 3600, 1),
+        Rational(time_of_day / 60 % 60, 1),
+        seconds,
+    ];
+    Ok((
+        Value::Text(format!("{:04}:{:02}:{:02}", year, month, day)),
+        Value::URational(time),
+    ))
+}
+
+/// Return the point in time a number of seconds and nanoseconds after
+/// midnight UTC of a date in the proleptic Gregorian calendar. The seconds
+/// may be negative or exceed a day.
+pub(crate) fn system_time_from_civil(
+    year: i64,
+    month: i64,
+    day: i64,
+    seconds: i64,
+    nanos: u32,
+) -> Option<SystemTime> {
+    let seconds = days_from_civil(year, month, day)
+        .checked_mul(SECONDS_PER_DAY)?
+        .checked_add(seconds)?;
+    let time = if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64))?
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))?
+    };
+    time.checked_add(Duration::from_nanos(u64::from(nanos)))
+}
+
+/// Return the number of days since 1970-01-01 of a date in the proleptic
+/// Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Return the year, month and day of a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
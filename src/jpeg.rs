@@ -0,0 +1,123 @@
+//! Minimal JPEG segment handling for embedding EXIF data.
+use std::ops::Range;
+
+const MARKER_SOI: u8 = 0xd8;
+const MARKER_EOI: u8 = 0xd9;
+const MARKER_SOS: u8 = 0xda;
+const MARKER_APP0: u8 = 0xe0;
+const MARKER_APP1: u8 = 0xe1;
+const MARKER_TEM: u8 = 0x01;
+
+/// Largest payload that fits in a JPEG segment, not counting the length.
+const MAX_PAYLOAD: usize = 0xffff - 2;
+
+/// A segment preceding the entropy-coded image data.
+struct Segment {
+    marker: u8,
+    /// Byte range of the segment, including any fill bytes before the marker.
+    range: Range<usize>,
+    /// Byte range of the payload, excluding the marker and length.
+    payload: Range<usize>,
+}
+
+/// Replace the EXIF APP1 segment of a JPEG image with the given raw EXIF
+/// block (which starts with the `Exif\0\0` header).
+///
+/// If the image has no EXIF segment, one is inserted after the start of image
+/// marker and any APP0 (JFIF) segments. All other bytes of the image are
+/// copied unchanged.
+pub(crate) fn replace_exif(jpeg: &[u8], exif: &[u8]) -> Result<Vec<u8>, super::Error> {
+    if exif.len() > MAX_PAYLOAD {
+        return Err(super::Error::ExifTooLarge);
+    }
+    if jpeg.len() < 2 || jpeg[0] != 0xff || jpeg[1] != MARKER_SOI {
+        return Err(super::Error::UnsupportedContainer);
+    }
+    let (segments, image_data) = parse_segments(jpeg)?;
+    let existing = segments.iter().position(|segment| {
+        segment.marker == MARKER_APP1 && jpeg[segment.payload.clone()].starts_with(b"Exif\0\0")
+    });
+    let insert_at = existing.unwrap_or_else(|| {
+        segments
+            .iter()
+            .take_while(|segment| segment.marker == MARKER_APP0)
+            .count()
+    });
+
+    let mut output = Vec::with_capacity(jpeg.len() + exif.len() + 4);
+    output.extend_from_slice(&jpeg[..2]);
+    for (index, segment) in segments.iter().enumerate() {
+        if index == insert_at {
+            write_exif_segment(&mut output, exif);
+            if existing.is_some() {
+                continue;
+            }
+        }
+        output.extend_from_slice(&jpeg[segment.range.clone()]);
+    }
+    if insert_at == segments.len() {
+        write_exif_segment(&mut output, exif);
+    }
+    output.extend_from_slice(&jpeg[image_data]);
+    Ok(output)
+}
+
+fn write_exif_segment(output: &mut Vec<u8>, exif: &[u8]) {
+    let length = (exif.len() + 2) as u16;
+    output.extend_from_slice(&[0xff, MARKER_APP1]);
+    output.extend_from_slice(&length.to_be_bytes());
+    output.extend_from_slice(exif);
+}
+
+/// Split the image into the segments that precede the image data, and the
+/// range of everything from the start of scan marker onwards.
+fn parse_segments(jpeg: &[u8]) -> Result<(Vec<Segment>, Range<usize>), super::Error> {
+    let mut segments = Vec::new();
+    let mut pos = 2;
+    while pos < jpeg.len() {
+        if jpeg[pos] != 0xff {
+            return Err(super::Error::UnsupportedContainer);
+        }
+        // any number of 0xff fill bytes may precede a marker
+        let mut marker_pos = pos + 1;
+        while marker_pos < jpeg.len() && jpeg[marker_pos] == 0xff {
+            marker_pos += 1;
+        }
+        if marker_pos >= jpeg.len() {
+            return Err(super::Error::Truncated);
+        }
+        let marker = jpeg[marker_pos];
+        if marker == MARKER_SOS || marker == MARKER_EOI {
+            return Ok((segments, pos..jpeg.len()));
+        }
+        if marker == MARKER_TEM || (0xd0..=0xd7).contains(&marker) {
+            // standalone markers have no length or payload
+            segments.push(Segment {
+                marker,
+                range: pos..marker_pos + 1,
+                payload: marker_pos + 1..marker_pos + 1,
+            });
+            pos = marker_pos + 1;
+            continue;
+        }
+        if marker_pos + 3 > jpeg.len() {
+            return Err(super::Error::Truncated);
+        }
+        let length = u16::from_be_bytes([jpeg[marker_pos + 1], jpeg[marker_pos + 2]]) as usize;
+        // the length includes its own two bytes
+        if length < 2 {
+            return Err(super::Error::UnsupportedContainer);
+        }
+        let end = marker_pos + 1 + length;
+        if end > jpeg.len() {
+            return Err(super::Error::Truncated);
+        }
+        segments.push(Segment {
+            marker,
+            range: pos..end,
+            payload: marker_pos + 3..end,
+        });
+        pos = end;
+    }
+    Ok((segments, pos..jpeg.len()))
+}
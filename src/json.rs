@@ -0,0 +1,571 @@
+//! Conversion to and from the JSON objects produced by `exiftool -j -G1`.
+use crate::bits::*;
+use crate::data::Data;
+use crate::diagnostic::{Diagnostic, LogCode};
+use crate::entry::Entry;
+use crate::gps::{read_coordinate, write_coordinate};
+use crate::tag::Tag;
+use crate::text::{parse_rational, read_comment, ASCII_COMMENT};
+use crate::value::{Rational, Value};
+use serde_json::{Map, Number};
+use std::fmt::Display;
+
+/// Group names used by exiftool for each IFD.
+const GROUPS: [(IFD, &str); 5] = [
+    (IFD::Image, "IFD0"),
+    (IFD::Thumbnail, "IFD1"),
+    (IFD::EXIF, "ExifIFD"),
+    (IFD::GPS, "GPS"),
+    (IFD::Interoperability, "InteropIFD"),
+];
+
+/// Group of the values exiftool derives from several tags, which are written
+/// but never applied.
+const COMPOSITE: &str = "Composite";
+
+/// Tags that exiftool names differently from libexif.
+const NAMES: [(IFD, Tag, &str); 21] = [
+    (IFD::Image, Tag::NEW_SUBFILE_TYPE, "SubfileType"),
+    (IFD::Image, Tag::IMAGE_LENGTH, "ImageHeight"),
+    (IFD::Image, Tag::DATE_TIME, "ModifyDate"),
+    (IFD::Image, Tag::PRINT_IMAGE_MATCHING, "PrintIM"),
+    (IFD::Thumbnail, Tag::IMAGE_LENGTH, "ImageHeight"),
+    (IFD::EXIF, Tag::DATE_TIME_DIGITIZED, "CreateDate"),
+    (IFD::EXIF, Tag::ISO_SPEED_RATINGS, "ISO"),
+    (IFD::EXIF, Tag::SUB_SEC_TIME, "SubSecTime"),
+    (IFD::EXIF, Tag::SUB_SEC_TIME_ORIGINAL, "SubSecTimeOriginal"),
+    (
+        IFD::EXIF,
+        Tag::SUB_SEC_TIME_DIGITIZED,
+        "SubSecTimeDigitized",
+    ),
+    (IFD::EXIF, Tag::EXPOSURE_BIAS_VALUE, "ExposureCompensation"),
+    (IFD::EXIF, Tag::PIXEL_X_DIMENSION, "ExifImageWidth"),
+    (IFD::EXIF, Tag::PIXEL_Y_DIMENSION, "ExifImageHeight"),
+    (IFD::EXIF, Tag::FLASH_PIX_VERSION, "FlashpixVersion"),
+    (
+        IFD::EXIF,
+        Tag::FOCAL_LENGTH_IN_35MM_FILM,
+        "FocalLengthIn35mmFormat",
+    ),
+    (IFD::EXIF, Tag::CAMERA_OWNER_NAME, "OwnerName"),
+    (IFD::EXIF, Tag::BODY_SERIAL_NUMBER, "SerialNumber"),
+    (IFD::EXIF, Tag::LENS_SPECIFICATION, "LensInfo"),
+    (
+        IFD::Interoperability,
+        Tag::INTEROPERABILITY_INDEX,
+        "InteropIndex",
+    ),
+    (
+        IFD::Interoperability,
+        Tag::INTEROPERABILITY_VERSION,
+        "InteropVersion",
+    ),
+    (
+        IFD::Interoperability,
+        Tag::RELATED_IMAGE_LENGTH,
+        "RelatedImageHeight",
+    ),
+];
+
+/// Tags that are neither exported nor imported, as libexif manages them.
+const SKIPPED: [Tag; 3] = [
+    Tag::MAKER_NOTE,
+    Tag::JPEG_INTERCHANGE_FORMAT,
+    Tag::JPEG_INTERCHANGE_FORMAT_LENGTH,
+];
+
+/// Names exiftool gives to the skipped tags and to the thumbnail image they
+/// locate.
+const SKIPPED_NAMES: [&str; 4] = [
+    "MakerNote",
+    "ThumbnailOffset",
+    "ThumbnailLength",
+    "ThumbnailImage",
+];
+
+/// GPS coordinates, which exiftool writes as unsigned decimal degrees, with
+/// their reference tags, the references of positive and negative values and
+/// the largest value.
+const COORDINATES: [(Tag, Tag, [&str; 2], f64); 4] = [
+    (Tag::GPS_LATITUDE, Tag::GPS_LATITUDE_REF, ["N", "S"], 90.0),
+    (
+        Tag::GPS_LONGITUDE,
+        Tag::GPS_LONGITUDE_REF,
+        ["E", "W"],
+        180.0,
+    ),
+    (
+        Tag::GPS_DEST_LATITUDE,
+        Tag::GPS_DEST_LATITUDE_REF,
+        ["N", "S"],
+        90.0,
+    ),
+    (
+        Tag::GPS_DEST_LONGITUDE,
+        Tag::GPS_DEST_LONGITUDE_REF,
+        ["E", "W"],
+        180.0,
+    ),
+];
+
+/// Tags holding text preceded by an 8-byte character code, which exiftool
+/// writes as plain text.
+const COMMENTS: [(IFD, Tag); 3] = [
+    (IFD::EXIF, Tag::USER_COMMENT),
+    (IFD::GPS, Tag::GPS_PROCESSING_METHOD),
+    (IFD::GPS, Tag::GPS_AREA_INFORMATION),
+];
+
+/// Largest binary value written as a list of bytes, as exiftool does for
+/// short values such as `ComponentsConfiguration` or `FileSource`.
+const MAX_BYTE_LIST: usize = 4;
+
+/// Domain of the diagnostics reported while applying JSON.
+const DOMAIN: &str = "ExifJson";
+
+/// Prefix of binary values encoded in base64, as written by `exiftool -b`.
+const BASE64_PREFIX: &str = "base64:";
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Style of the values in exiftool-style JSON.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum JsonMode {
+    /// Raw values, like `exiftool -n`. Numbers, and text that looks like a
+    /// plain number, are written as JSON numbers, values with several
+    /// components as space-separated strings, and binary data as base64 with
+    /// a `base64:` prefix. As with exiftool, GPS coordinates are written as
+    /// unsigned decimal degrees, with the signed latitude and longitude as
+    /// `Composite:GPSLatitude` and `Composite:GPSLongitude`, `GPSTimeStamp` as
+    /// `hh:mm:ss`, the APEX shutter speed and apertures as seconds and
+    /// f-numbers, comments as text and binary values of up to 4 bytes as
+    /// numbers.
+    Numeric,
+    /// Values as formatted by libexif, such as `1/250 sec.` or `Top-left`.
+    /// Values that look like plain numbers are written as JSON numbers.
+    Printable,
+}
+
+/// Build an exiftool-style object from the entries of all IFDs.
+pub(crate) fn export(data: &Data, mode: JsonMode) -> Map<String, serde_json::Value> {
+    let byte_order = data.byte_order().ok();
+    let mut object = Map::new();
+    for (ifd, group) in GROUPS {
+        let content = data.content(ifd);
+        for entry in content.entries() {
+            let tag = entry.tag();
+            if SKIPPED.contains(&tag) {
+                continue;
+            }
+            let value = match mode {
+                JsonMode::Numeric => {
+                    byte_order.and_then(|byte_order| numeric(ifd, &entry, byte_order))
+                }
+                JsonMode::Printable => entry.text_value().ok().map(printable),
+            };
+            if let Some(value) = value {
+                object.insert(format!("{}:{}", group, tag_name(ifd, tag)), value);
+            }
+        }
+    }
+    if mode == JsonMode::Numeric {
+        let gps = data.gps().unwrap_or_default();
+        for (name, degrees) in [
+            ("GPSLatitude", gps.latitude),
+            ("GPSLongitude", gps.longitude),
+        ] {
+            if let Some(number) = degrees.map(significant).and_then(Number::from_f64) {
+                object.insert(format!("{}:{}", COMPOSITE, name), number.into());
+            }
+        }
+    }
+    object
+}
+
+/// Apply the entries of an exiftool-style object, or of the first object of
+/// an array of them.
+pub(crate) fn import(data: &mut Data, json: &serde_json::Value) -> Result<(), super::Error> {
+    let object = match json {
+        serde_json::Value::Array(array) => array.first().and_then(|value| value.as_object()),
+        _ => json.as_object(),
+    };
+    let object = object.ok_or_else(|| super::Error::InvalidJson(String::new()))?;
+    // convert every value before applying any, so that the data is left
+    // unchanged if one of them is invalid
+    let mut changes = Vec::new();
+    for (key, json_value) in object {
+        let Some((group, name)) = key.split_once(':') else {
+            continue;
+        };
+        let Some(ifd) = GROUPS
+            .iter()
+            .find(|(_, other)| *other == group)
+            .map(|(ifd, _)| *ifd)
+        else {
+            continue;
+        };
+        if SKIPPED_NAMES.contains(&name) {
+            continue;
+        }
+        let Some(tag) = tag_from_name(ifd, name) else {
+            data.report(Diagnostic::new(
+                LogCode::None,
+                DOMAIN,
+                format!("Skipping '{}': unknown tag.", key),
+            ));
+            continue;
+        };
+        if SKIPPED.contains(&tag) {
+            continue;
+        }
+        if json_value.is_null() {
+            changes.push((ifd, tag, None));
+            continue;
+        }
+        let invalid = || super::Error::InvalidJson(key.clone());
+        let coordinate = COORDINATES
+            .iter()
+            .find(|(other, ..)| ifd == IFD::GPS && *other == tag);
+        if let (Some((_, reference, references, max)), Some(degrees)) =
+            (coordinate, decimal(json_value))
+        {
+            let (reference_value, dms) =
+                write_coordinate(degrees, *max, *references).map_err(|_| invalid())?;
+            changes.push((ifd, tag, Some(dms)));
+            // a reference given alongside an unsigned value takes precedence
+            let reference_key = format!("{}:{}", group, tag_name(ifd, *reference));
+            if !object.contains_key(&reference_key) {
+                changes.push((ifd, *reference, Some(reference_value)));
+            }
+            continue;
+        }
+        let json_value = unconvert(ifd, tag, json_value).ok_or_else(invalid)?;
+        let value = data
+            .content(ifd)
+            .build_value(tag, DataType::Text, |data_type| {
+                parse(&json_value, data_type, COMMENTS.contains(&(ifd, tag)))
+            })
+            .ok_or_else(invalid)?;
+        changes.push((ifd, tag, Some(value)));
+    }
+    for (ifd, tag, value) in changes {
+        let mut content = data.content_mut(ifd);
+        match value {
+            Some(value) => {
+                content.insert_value(tag, value)?;
+            }
+            None => {
+                content.remove(tag);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn tag_name(ifd: IFD, tag: Tag) -> String {
+    if let Some((_, _, name)) = NAMES
+        .iter()
+        .find(|(other_ifd, other_tag, _)| *other_ifd == ifd && *other_tag == tag)
+    {
+        return name.to_string();
+    }
+    match tag.name(ifd) {
+        Ok(name) => name.to_string(),
+        Err(_) => format!("Exif_0x{:04x}", tag.code()),
+    }
+}
+
+fn tag_from_name(ifd: IFD, name: &str) -> Option<Tag> {
+    if let Some((_, tag, _)) = NAMES
+        .iter()
+        .find(|(other_ifd, _, other_name)| *other_ifd == ifd && *other_name == name)
+    {
+        return Some(*tag);
+    }
+    if let Some(code) = name.strip_prefix("Exif_0x") {
+        return u16::from_str_radix(code, 16)
+            .ok()
+            .map(|code| Tag::from(code as u32));
+    }
+    Tag::from_name(name)
+}
+
+fn numeric(ifd: IFD, entry: &Entry, byte_order: ByteOrder) -> Option<serde_json::Value> {
+    let tag = entry.tag();
+    let (value, _) = entry.value_lenient(byte_order).ok()?;
+    if let Some(converted) = convert(ifd, tag, &value) {
+        return Some(converted);
+    }
+    Some(match value {
+        Value::Text(text) => printable(text.trim_end().to_string()),
+        Value::U8(v) => numbers(&v),
+        Value::I8(v) => numbers(&v),
+        Value::U16(v) => numbers(&v),
+        Value::I16(v) => numbers(&v),
+        Value::U32(v) => numbers(&v),
+        Value::I32(v) => numbers(&v),
+        Value::URational(v) => rationals(&v),
+        Value::IRational(v) => rationals(&v),
+        Value::Undefined(bytes) => {
+            if bytes.iter().all(|byte| (0x20..0x7f).contains(byte)) {
+                String::from_utf8_lossy(&bytes).into_owned().into()
+            } else if bytes.len() <= MAX_BYTE_LIST {
+                numbers(&bytes)
+            } else {
+                format!("{}{}", BASE64_PREFIX, base64_encode(&bytes)).into()
+            }
+        }
+    })
+}
+
+/// Convert a value the way `exiftool -n` does for the tags it does not write
+/// as raw values, returning `None` for other tags or if the value is not in
+/// the expected form.
+fn convert(ifd: IFD, tag: Tag, value: &Value) -> Option<serde_json::Value> {
+    if ifd == IFD::GPS {
+        if COORDINATES.iter().any(|(other, ..)| *other == tag) {
+            // the sign is left to the reference tag
+            let degrees = read_coordinate(Some(value.clone()), None, '-')?;
+            return Number::from_f64(significant(degrees)).map(serde_json::Value::Number);
+        }
+        if tag == Tag::GPS_TIME_STAMP {
+            return time_stamp(value).map(Into::into);
+        }
+    }
+    if COMMENTS.contains(&(ifd, tag)) {
+        return read_comment(value).map(Into::into);
+    }
+    if ifd == IFD::EXIF {
+        let apex = match value {
+            Value::URational(v) => v.first()?.to_f64()?,
+            Value::IRational(v) => v.first()?.to_f64()?,
+            _ => return None,
+        };
+        let converted = match tag {
+            Tag::SHUTTER_SPEED_VALUE if apex.abs() < 100.0 => 2f64.powf(-apex),
+            Tag::APERTURE_VALUE | Tag::MAX_APERTURE_VALUE => 2f64.powf(apex / 2.0),
+            _ => return None,
+        };
+        return Number::from_f64(converted).map(serde_json::Value::Number);
+    }
+    None
+}
+
+/// Undo the conversion of [`convert()`], returning the value unchanged for
+/// other tags and `None` if it is invalid.
+fn unconvert(ifd: IFD, tag: Tag, json: &serde_json::Value) -> Option<serde_json::Value> {
+    match (ifd, tag) {
+        (IFD::GPS, Tag::GPS_TIME_STAMP) => match json.as_str() {
+            Some(text) if text.contains(':') => Some(text.replace(':', " ").into()),
+            _ => Some(json.clone()),
+        },
+        (IFD::EXIF, Tag::SHUTTER_SPEED_VALUE) => {
+            let seconds = decimal(json).filter(|seconds| *seconds > 0.0)?;
+            Number::from_f64(-seconds.log2()).map(serde_json::Value::Number)
+        }
+        (IFD::EXIF, Tag::APERTURE_VALUE | Tag::MAX_APERTURE_VALUE) => {
+            let f_number = decimal(json).filter(|f_number| *f_number > 0.0)?;
+            Number::from_f64(2.0 * f_number.log2()).map(serde_json::Value::Number)
+        }
+        _ => Some(json.clone()),
+    }
+}
+
+/// Round a number to the 15 significant digits exiftool writes, so that
+/// coordinates read back from degrees, minutes and seconds come out as they
+/// were written.
+fn significant(number: f64) -> f64 {
+    format!("{:.14e}", number).parse().unwrap_or(number)
+}
+
+/// Read a JSON number, or a string holding a single one.
+fn decimal(json: &serde_json::Value) -> Option<f64> {
+    match json {
+        serde_json::Value::Number(number) => number.as_f64(),
+        serde_json::Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Write the hours, minutes and seconds of a `GPSTimeStamp` as `hh:mm:ss`,
+/// with the fraction of a second if any.
+fn time_stamp(value: &Value) -> Option<String> {
+    let Value::URational(parts) = value else {
+        return None;
+    };
+    let [hours, minutes, seconds] = parts.as_slice() else {
+        return None;
+    };
+    let total = (hours.to_f64()? * 3600.0 + minutes.to_f64()? * 60.0 + seconds.to_f64()?) * 1e6;
+    let micros = total.round() as u64;
+    let mut text = format!(
+        "{:02}:{:02}:{:02}",
+        micros / 3_600_000_000,
+        micros / 60_000_000 % 60,
+        micros / 1_000_000 % 60
+    );
+    let fraction = micros % 1_000_000;
+    if fraction > 0 {
+        let fraction = format!("{:06}", fraction);
+        text.push('.');
+        text.push_str(fraction.trim_end_matches('0'));
+    }
+    Some(text)
+}
+
+/// Write a single number as a JSON number and several as a string.
+fn numbers<T: Copy + Into<Number> + ToString>(values: &[T]) -> serde_json::Value {
+    match values {
+        [value] => serde_json::Value::Number((*value).into()),
+        _ => join(values.iter().map(ToString::to_string)),
+    }
+}
+
+fn rationals<T: Copy + Into<f64> + Display>(values: &[Rational<T>]) -> serde_json::Value {
+    let decimal = |rational: &Rational<T>| {
+        rational
+            .to_f64()
+            .and_then(Number::from_f64)
+            .ok_or_else(|| rational.to_string())
+    };
+    match values {
+        [value] => match decimal(value) {
+            Ok(number) => serde_json::Value::Number(number),
+            Err(text) => text.into(),
+        },
+        _ => join(
+            values
+                .iter()
+                .map(|value| decimal(value).map_or_else(|text| text, |number| number.to_string())),
+        ),
+    }
+}
+
+fn join<I: Iterator<Item = String>>(values: I) -> serde_json::Value {
+    values.collect::<Vec<_>>().join(" ").into()
+}
+
+/// Write text that looks like a plain number as a JSON number.
+fn printable(text: String) -> serde_json::Value {
+    let digits = text.strip_prefix('-').unwrap_or(&text);
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, "0"));
+    let plain = !integer.is_empty()
+        && !fraction.is_empty()
+        && (integer == "0" || !integer.starts_with('0'))
+        && integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit());
+    if plain {
+        if let Ok(number) = text.parse::<i64>() {
+            return number.into();
+        }
+        if let Some(number) = text.parse::<f64>().ok().and_then(Number::from_f64) {
+            return serde_json::Value::Number(number);
+        }
+    }
+    text.into()
+}
+
+/// Convert a JSON value in the numeric style to a value of the given type.
+/// Text for a comment is preceded by the ASCII character code.
+fn parse(json: &serde_json::Value, data_type: DataType, comment: bool) -> Option<Value> {
+    let text = match json {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Number(number) => number.to_string(),
+        _ => return None,
+    };
+    let tokens = || text.split_whitespace();
+    Some(match data_type {
+        DataType::Text => Value::Text(text.clone()),
+        DataType::U8 => Value::U8(integers(tokens())?),
+        DataType::I8 => Value::I8(integers(tokens())?),
+        DataType::U16 => Value::U16(integers(tokens())?),
+        DataType::I16 => Value::I16(integers(tokens())?),
+        DataType::U32 => Value::U32(integers(tokens())?),
+        DataType::I32 => Value::I32(integers(tokens())?),
+        DataType::URational => Value::URational(
+            tokens()
+                .map(|token| {
+                    let (numerator, denominator) = parse_rational(token)?;
+                    Some(Rational(
+                        u32::try_from(numerator).ok()?,
+                        u32::try_from(denominator).ok()?,
+                    ))
+                })
+                .collect::<Option<_>>()?,
+        ),
+        DataType::IRational => Value::IRational(
+            tokens()
+                .map(|token| {
+                    let (numerator, denominator) = parse_rational(token)?;
+                    Some(Rational(
+                        i32::try_from(numerator).ok()?,
+                        i32::try_from(denominator).ok()?,
+                    ))
+                })
+                .collect::<Option<_>>()?,
+        ),
+        DataType::Undefined => match text.strip_prefix(BASE64_PREFIX) {
+            Some(encoded) => Value::Undefined(base64_decode(encoded)?),
+            None if comment => Value::Undefined([ASCII_COMMENT, text.as_bytes()].concat()),
+            // numbers are bytes, as are several of them in a string
+            None if json.is_number() || tokens().nth(1).is_some() => match integers(tokens()) {
+                Some(bytes) => Value::Undefined(bytes),
+                None if json.is_number() => return None,
+                None => Value::Undefined(text.as_bytes().to_vec()),
+            },
+            None => Value::Undefined(text.as_bytes().to_vec()),
+        },
+    })
+}
+
+fn integers<'a, T: TryFrom<i64>, I: Iterator<Item = &'a str>>(tokens: I) -> Option<Vec<T>> {
+    let values = tokens
+        .map(|token| T::try_from(token.parse::<i64>().ok()?).ok())
+        .collect::<Option<Vec<_>>>()?;
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() / 3 * 4 + 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (index, byte)| {
+            bits | (*byte as u32) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (bits >> (18 - 6 * index)) & 0x3f;
+                encoded.push(BASE64_ALPHABET[sextet as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.trim_end_matches('=').as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    for chunk in encoded.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut bits = 0u32;
+        for (index, symbol) in chunk.iter().enumerate() {
+            let sextet = BASE64_ALPHABET.iter().position(|other| other == symbol)?;
+            bits |= (sextet as u32) << (18 - 6 * index);
+        }
+        for index in 0..chunk.len() - 1 {
+            bytes.push((bits >> (16 - 8 * index)) as u8);
+        }
+    }
+    Some(bytes)
+}
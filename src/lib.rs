@@ -13,9 +13,8 @@
 //! [`entries`](struct.Entry.html):
 //!
 //! ```
-//! # use std::io;
 //! # use std::path::Path;
-//! fn dump_exif<P: AsRef<Path>>(file_name: P) -> io::Result<()> {
+//! fn dump_exif<P: AsRef<Path>>(file_name: P) -> Result<(), libexif::Error> {
 //!     let data = libexif::Data::open(file_name)?;
 //!     for content in data.contents() {
 //!         let ifd = content.ifd().unwrap();
@@ -51,24 +50,105 @@ pub enum Error {
     IllegalSupportLevel,
     #[error("unknown IFD value")]
     UnknownIFD,
+    #[error("value type not allowed for tag")]
+    IllegalValueType,
+    #[error("out of memory")]
+    OutOfMemory,
+    #[error("thumbnail is not a JPEG image")]
+    InvalidThumbnail,
+    #[error("memory or entry limit exceeded")]
+    LimitExceeded,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("no EXIF data found")]
+    NoExifFound,
+    #[error("EXIF data is truncated")]
+    Truncated,
+    #[error("unsupported image format")]
+    UnsupportedContainer,
+    #[error("EXIF data too large for a JPEG segment")]
+    ExifTooLarge,
+    #[error("invalid entry for tag {:#06x} in {:?} IFD", .tag.code(), .ifd)]
+    InvalidEntry { ifd: IFD, tag: Tag },
+    #[error(
+        "data of tag {:#06x} in {:?} IFD has {} bytes instead of {}",
+        .tag.code(), .ifd, .actual_size, .expected_size
+    )]
+    EntrySizeMismatch {
+        ifd: IFD,
+        tag: Tag,
+        expected_size: usize,
+        actual_size: usize,
+    },
+    #[error("tag not known to libexif")]
+    UnknownTag,
+    #[error("text is not valid UTF-8")]
+    InvalidText(#[from] std::str::Utf8Error),
+    #[error("illegal orientation value")]
+    IllegalOrientation,
+    #[error("GPS coordinate or timestamp out of range")]
+    InvalidGps,
+    #[error("cannot apply JSON entry {0:?}")]
+    InvalidJson(String),
+    #[error("invalid XMP packet: {0}")]
+    InvalidXmp(String),
+}
+
+impl From<Error> for std::io::Error {
+    /// Unwrap I/O errors and report any other error as invalid data.
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            err => std::io::Error::new(std::io::ErrorKind::InvalidData, err),
+        }
+    }
 }
 
 pub use bits::*;
+pub use camera::*;
 pub use content::*;
 pub use data::*;
+#[cfg(feature = "chrono")]
+pub use datetime::*;
+pub use diagnostic::*;
 pub use entry::*;
+pub use gps::*;
+#[cfg(feature = "json")]
+pub use json::JsonMode;
+pub use loader::*;
+pub use maker_note::*;
+pub use mem::*;
+pub use orientation::*;
 pub use tag::*;
 pub use value::*;
 
 mod internal;
 
 mod bits;
+mod camera;
 mod content;
 mod data;
+#[cfg(feature = "chrono")]
+mod datetime;
+mod diagnostic;
 mod entry;
+mod gps;
+mod jpeg;
+#[cfg(feature = "json")]
+mod json;
 mod loader;
+mod maker_note;
+mod mem;
+mod orientation;
+#[cfg(feature = "serde")]
+mod serialize;
 mod tag;
+#[cfg(any(feature = "chrono", feature = "json", feature = "xmp"))]
+mod text;
+mod tiff;
 mod value;
+#[cfg(feature = "xmp")]
+mod xmp;
 
 #[cfg(test)]
 mod tests {
@@ -101,17 +181,1030 @@ mod tests {
         let data = Data::open("tests/fixtures/f2t.jpg")?;
         let byte_order = data.byte_order().unwrap();
         assert_eq!(byte_order, ByteOrder::LittleEndian);
-        for content in data.contents() {
+        let entry = data.get(IFD::Image, Tag::ORIENTATION).unwrap();
+        assert_eq!(entry.value(byte_order).unwrap(), Value::U16(vec![2]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_orientation() -> io::Result<()> {
+        let data = Data::open("tests/fixtures/f2t.jpg")?;
+        let byte_order = data.byte_order().unwrap();
+        let entry = data.find(Tag::ORIENTATION).unwrap();
+        match entry.value(byte_order).unwrap() {
+            Value::U16(v) => assert_eq!(v, vec![2]),
+            _ => panic!("wrong type of value"),
+        }
+        assert!(data.get(IFD::Image, Tag::ORIENTATION).is_some());
+        assert!(data.get(IFD::EXIF, Tag::ORIENTATION).is_none());
+        let content = data.content(IFD::Image);
+        assert_eq!(content.ifd().unwrap(), IFD::Image);
+        assert!(content.get(Tag::ORIENTATION).is_some());
+        assert!(data.find(Tag::ARTIST).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_from_name() {
+        assert_eq!(Tag::from_name("Orientation"), Some(Tag::ORIENTATION));
+        assert_eq!(Tag::from_name("GPSVersionID"), Some(Tag::GPS_VERSION_ID));
+        assert_eq!(Tag::ORIENTATION.code(), 274);
+        assert_eq!(Tag::from_name("NoSuchTag"), None);
+        assert_eq!(Tag::from_name("Orien\0tation"), None);
+        assert_eq!(Tag::ORIENTATION.name(IFD::Image).unwrap(), "Orientation");
+        assert!(matches!(
+            Tag::from(0xfffe).name(IFD::Image),
+            Err(Error::UnknownTag)
+        ));
+    }
+
+    #[test]
+    fn test_tag_table() {
+        let table: Vec<TagInfo> = Tag::table().collect();
+        assert!(table.len() > 100);
+        let orientation = table.iter().find(|t| t.name() == "Orientation").unwrap();
+        assert_eq!(orientation.tag(), Tag::ORIENTATION);
+        assert_eq!(orientation.title(), "Orientation");
+        assert!(!orientation.description().is_empty());
+        assert_eq!(
+            orientation.support_level(IFD::GPS, DataEncoding::Chunky),
+            SupportLevel::NotAllowed
+        );
+        // the same code means different things in the GPS and interoperability IFDs
+        let latitude_ref = table.iter().find(|t| t.name() == "GPSLatitudeRef").unwrap();
+        assert_eq!(latitude_ref.tag(), Tag::GPS_LATITUDE_REF);
+        assert_ne!(
+            latitude_ref.support_level(IFD::GPS, DataEncoding::Chunky),
+            SupportLevel::NotAllowed
+        );
+        assert_eq!(
+            latitude_ref.support_level(IFD::Interoperability, DataEncoding::Chunky),
+            SupportLevel::NotAllowed
+        );
+    }
+
+    #[test]
+    fn test_to_bytes() -> io::Result<()> {
+        let data = Data::open("tests/fixtures/f2t.jpg")?;
+        let bytes = data.to_bytes();
+        assert_eq!(&bytes[..6], b"Exif\0\0");
+        // byte order of the fixture is little-endian (Intel)
+        assert_eq!(&bytes[6..10], b"II*\0");
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_bytes() -> io::Result<()> {
+        let data = Data::open("tests/fixtures/f2t.jpg")?;
+        let bytes = data.to_bytes();
+        let copy = Data::from_bytes(&bytes)?;
+        assert_eq!(copy.byte_order().unwrap(), ByteOrder::LittleEndian);
+        let counts: Vec<usize> = data.contents().map(|c| c.len()).collect();
+        let copy_counts: Vec<usize> = copy.contents().map(|c| c.len()).collect();
+        assert_eq!(counts, copy_counts);
+        // also accepts the TIFF structure without the EXIF header
+        let tiff = Data::from_bytes(&bytes[6..])?;
+        assert_eq!(tiff.contents().map(|c| c.len()).sum::<usize>(), counts.iter().sum::<usize>());
+        assert!(matches!(
+            Data::from_bytes(b"not EXIF data"),
+            Err(Error::UnsupportedContainer)
+        ));
+        // a well-formed block with an empty IFD 0 is not an error
+        let empty = Data::from_bytes(b"Exif\0\0II*\0\x08\0\0\0\0\0\0\0\0\0")?;
+        assert!(empty.contents().all(|c| c.len() == 0));
+        assert!(matches!(
+            Data::from_bytes(b"Exif\0\0"),
+            Err(Error::NoExifFound)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_reader() -> io::Result<()> {
+        let bytes = std::fs::read("tests/fixtures/f2t.jpg")?;
+        let data = Data::from_reader(io::Cursor::new(bytes))?;
+        assert_eq!(data.byte_order().unwrap(), ByteOrder::LittleEndian);
+        assert!(matches!(
+            Data::from_reader(io::Cursor::new(vec![0u8; 64])),
+            Err(Error::UnsupportedContainer)
+        ));
+        // the stream ends within the EXIF segment at 0x14..0x38
+        let bytes = std::fs::read("tests/fixtures/f2t.jpg")?;
+        assert!(matches!(
+            Data::from_reader(io::Cursor::new(&bytes[..0x30])),
+            Err(Error::Truncated)
+        ));
+        assert!(matches!(
+            Data::open("tests/fixtures/missing.jpg"),
+            Err(Error::Io(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_loader_write() -> io::Result<()> {
+        use std::io::Write;
+        let bytes = std::fs::read("tests/fixtures/f2t.jpg")?;
+        let mut loader = Loader::new();
+        for _ in 0..2 {
+            // feed the image in small chunks until the loader has enough
+            for chunk in bytes.chunks(7) {
+                loader.write_all(chunk)?;
+                if !loader.needs_more() {
+                    break;
+                }
+            }
+            assert!(!loader.needs_more());
+            assert!(loader.raw_data().starts_with(b"Exif\0\0"));
+            assert!(loader.data().is_some());
+            loader.reset();
+            assert!(loader.needs_more());
+            assert!(loader.raw_data().is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_to_jpeg() -> io::Result<()> {
+        let jpeg = std::fs::read("tests/fixtures/f2t.jpg")?;
+        // the fixture's EXIF segment occupies bytes 0x14 to 0x38
+        assert_eq!(&jpeg[0x14..0x1e], b"\xff\xe1\x00\x22Exif\0\0");
+        let data = Data::open("tests/fixtures/f2t.jpg")?;
+        let exif = data.to_bytes();
+        let output = data.write_to_jpeg_bytes(&jpeg)?;
+        assert_eq!(&output[..0x14], &jpeg[..0x14]);
+        assert_eq!(&output[0x18..0x18 + exif.len()], &exif[..]);
+        assert_eq!(&output[0x18 + exif.len()..], &jpeg[0x38..]);
+        let copy = Data::from_reader(io::Cursor::new(&output))?;
+        assert!(copy.find(Tag::ORIENTATION).is_some());
+
+        // a new segment is inserted after the JFIF segment
+        let stripped = [&jpeg[..0x14], &jpeg[0x38..]].concat();
+        assert!(matches!(
+            Data::from_reader(io::Cursor::new(&stripped)),
+            Err(Error::NoExifFound)
+        ));
+        let inserted = data.write_to_jpeg_bytes(&stripped)?;
+        assert_eq!(inserted, output);
+
+        assert!(matches!(
+            data.write_to_jpeg_bytes(b"not a JPEG image"),
+            Err(Error::UnsupportedContainer)
+        ));
+        // a segment length cannot be shorter than the length field itself
+        assert!(matches!(
+            data.write_to_jpeg_bytes(b"\xff\xd8\xff\xe0\x00\x01\xff\xda"),
+            Err(Error::UnsupportedContainer)
+        ));
+        assert!(matches!(
+            data.write_to_jpeg_bytes(b"\xff\xd8\xff\xe0\x00\x10JFIF"),
+            Err(Error::Truncated)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumbnail() -> io::Result<()> {
+        let jpeg = std::fs::read("tests/fixtures/f2t.jpg")?;
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        let byte_order = data.byte_order().unwrap();
+        assert!(data.thumbnail().is_none());
+        assert!(data.set_thumbnail(b"not a JPEG".to_vec()).is_err());
+        data.set_thumbnail(jpeg.clone()).unwrap();
+        assert_eq!(data.thumbnail(), Some(&jpeg[..]));
+        let compression = data.get(IFD::Thumbnail, Tag::COMPRESSION).unwrap();
+        match compression.value(byte_order).unwrap() {
+            Value::U16(v) => assert_eq!(v, vec![6]),
+            _ => panic!("wrong type of value"),
+        }
+
+        let mut copy = Data::from_bytes(&data.to_bytes())?;
+        assert_eq!(copy.thumbnail(), Some(&jpeg[..]));
+        copy.remove_thumbnail();
+        assert!(copy.thumbnail().is_none());
+        assert!(copy.get(IFD::Thumbnail, Tag::COMPRESSION).is_none());
+        let copy = Data::from_bytes(&copy.to_bytes())?;
+        assert!(copy.thumbnail().is_none());
+        Ok(())
+    }
+
+    /// Build EXIF data with a minimal Canon maker note containing only the
+    /// ImageType tag.
+    fn canon_data() -> io::Result<Data> {
+        // one entry: ImageType (6), ASCII, 4 components stored inline
+        let mut note = vec![1, 0, 6, 0, 2, 0, 4, 0, 0, 0];
+        note.extend_from_slice(b"ABC\0");
+        note.extend_from_slice(&[0, 0, 0, 0]);
+        maker_note_data("Canon", note)
+    }
+
+    fn maker_note_data(make: &str, note: Vec<u8>) -> io::Result<Data> {
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        data.content_mut(IFD::Image)
+            .insert_value(Tag::MAKE, Value::Text(make.into()))
+            .unwrap();
+        data.content_mut(IFD::EXIF)
+            .insert_value(Tag::MAKER_NOTE, Value::Undefined(note))
+            .unwrap();
+        // libexif interprets the maker note when loading
+        Ok(Data::from_bytes(&data.to_bytes())?)
+    }
+
+    #[test]
+    fn test_maker_note() -> io::Result<()> {
+        let data = Data::open("tests/fixtures/f2t.jpg")?;
+        assert!(data.maker_note().is_none());
+        let data = canon_data()?;
+        let maker_note = data.maker_note().unwrap();
+        assert_eq!(maker_note.len(), 1);
+        let entry = maker_note.entries().next().unwrap();
+        assert_eq!(entry.id(), 6);
+        assert_eq!(entry.name(), Some("ImageType"));
+        assert_eq!(entry.value(), Some("ABC"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_maker_note_value() -> io::Result<()> {
+        let text = "A".repeat(2000);
+        // one entry: ImageType (6), ASCII, stored after the IFD at an offset
+        // relative to the TIFF header
+        let note = |offset: u32| {
+            let mut note = vec![1, 0, 6, 0, 2, 0];
+            note.extend_from_slice(&(text.len() as u32 + 1).to_le_bytes());
+            note.extend_from_slice(&offset.to_le_bytes());
+            note.extend_from_slice(&[0, 0, 0, 0]);
+            note.extend_from_slice(text.as_bytes());
+            note.push(0);
+            note
+        };
+        // find where the note ends up, which does not depend on its content
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        data.content_mut(IFD::Image)
+            .insert_value(Tag::MAKE, Value::Text("Canon".into()))
+            .unwrap();
+        data.content_mut(IFD::EXIF)
+            .insert_value(Tag::MAKER_NOTE, Value::Undefined(note(0)))
+            .unwrap();
+        let bytes = data.to_bytes();
+        let position = bytes
+            .windows(text.len())
+            .position(|window| window == text.as_bytes())
+            .unwrap();
+        // the bytes start with the 6 byte EXIF header
+        let data = maker_note_data("Canon", note(position as u32 - 6))?;
+        let maker_note = data.maker_note().unwrap();
+        let entry = maker_note.entries().next().unwrap();
+        assert_eq!(entry.value(), Some(text.as_str()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_maker_note() -> io::Result<()> {
+        let data = canon_data()?;
+        match data.maker_note().unwrap() {
+            MakerNote::Canon(canon) => {
+                assert_eq!(canon.image_type().as_deref(), Some("ABC"));
+                assert_eq!(canon.macro_mode(), None);
+            }
+            _ => panic!("expected a Canon maker note"),
+        }
+        // CameraSettings (1), SHORT, 2 components: the size in bytes and the
+        // macro mode
+        let mut note = vec![1, 0, 1, 0, 3, 0, 2, 0, 0, 0, 4, 0, 1, 0];
+        note.extend_from_slice(&[0, 0, 0, 0]);
+        let data = maker_note_data("Canon", note)?;
+        match data.maker_note().unwrap() {
+            MakerNote::Canon(canon) => {
+                assert_eq!(canon.camera_settings(), Some(vec![4, 1]));
+                assert_eq!(canon.macro_mode(), Some(CanonMacroMode::Macro));
+            }
+            _ => panic!("expected a Canon maker note"),
+        }
+        // FilmMode (0x1401), SHORT, 1 component
+        let mut note = b"FUJIFILM".to_vec();
+        note.extend_from_slice(&[12, 0, 0, 0, 1, 0, 0x01, 0x14, 3, 0, 1, 0, 0, 0]);
+        note.extend_from_slice(&[0, 0x06, 0, 0, 0, 0, 0, 0]);
+        let data = maker_note_data("FUJIFILM", note)?;
+        match data.maker_note().unwrap() {
+            MakerNote::Fuji(fuji) => {
+                assert_eq!(fuji.film_simulation(), Some(FilmSimulation::ClassicChrome));
+            }
+            _ => panic!("expected a Fuji maker note"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_diagnostics() -> io::Result<()> {
+        let (mut data, _) = Data::open_with_diagnostics("tests/fixtures/f2t.jpg")?;
+        assert!(data.take_diagnostics().is_empty());
+        // fix() reports each mandatory tag that it adds
+        data.content_mut(IFD::EXIF).remove(Tag::EXIF_VERSION);
+        data.fix();
+        let diagnostics = data.take_diagnostics();
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.message().contains("ExifVersion")));
+        assert!(data.take_diagnostics().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_budget() -> io::Result<()> {
+        let budget = MemoryBudget::new(1 << 20, 1000);
+        let file = std::fs::File::open("tests/fixtures/f2t.jpg")?;
+        let data = Data::from_reader_with_budget(file, &budget).unwrap();
+        assert!(budget.current() > 0);
+        assert!(budget.peak() >= budget.current());
+        let bytes = data.to_bytes();
+        drop(data);
+        assert_eq!(budget.current(), 0);
+        // too little memory
+        let budget = MemoryBudget::new(64, 1000);
+        assert!(matches!(
+            Data::from_bytes_with_budget(&bytes, &budget),
+            Err(Error::LimitExceeded)
+        ));
+        // too many entries
+        let budget = MemoryBudget::new(1 << 20, 2);
+        assert!(matches!(
+            Data::from_bytes_with_budget(&bytes, &budget),
+            Err(Error::LimitExceeded)
+        ));
+        assert_eq!(budget.current(), 0);
+        assert!(budget.peak() > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone() -> io::Result<()> {
+        let data = Data::open("tests/fixtures/f2t.jpg")?;
+        let mut copy = data.clone();
+        let counts: Vec<usize> = data.contents().map(|c| c.len()).collect();
+        let copy_counts: Vec<usize> = copy.contents().map(|c| c.len()).collect();
+        assert_eq!(counts, copy_counts);
+        copy.content_mut(IFD::Image)
+            .insert_value(Tag::MAKE, Value::Text("Copy".into()))
+            .unwrap();
+        let make = |data: &Data| {
+            data.get(IFD::Image, Tag::MAKE)
+                .map(|e| e.text_value().unwrap())
+        };
+        assert_ne!(make(&data).as_deref(), Some("Copy"));
+        // the copy can be handed to another thread
+        let handle = std::thread::spawn(move || make(&copy));
+        assert_eq!(handle.join().unwrap().as_deref(), Some("Copy"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_size_mismatch() -> Result<(), Error> {
+        let data = Data::open("tests/fixtures/f2t.jpg")?;
+        let byte_order = data.byte_order()?;
+        let entry = data.get(IFD::Image, Tag::ORIENTATION).unwrap();
+        // claim more components than the data holds
+        unsafe { (*entry.as_ptr()).components = 3 };
+        assert!(matches!(
+            entry.value(byte_order),
+            Err(Error::EntrySizeMismatch {
+                ifd: IFD::Image,
+                tag: Tag::ORIENTATION,
+                expected_size: 6,
+                actual_size: 2
+            })
+        ));
+        match entry.value_lenient(byte_order)? {
+            (Value::U16(v), mismatch) => {
+                assert_eq!(v, vec![2]);
+                assert_eq!(
+                    mismatch,
+                    Some(SizeMismatch {
+                        expected_size: 6,
+                        actual_size: 2
+                    })
+                );
+            }
+            _ => panic!("wrong type of value"),
+        }
+        // text without a terminating null byte
+        match Value::extract(b"abc", DataType::Text, 3, byte_order).unwrap() {
+            Value::Text(text) => assert_eq!(text, "abc"),
+            _ => panic!("wrong type of value"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_value() -> Result<(), Error> {
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        let mut content = data.content_mut(IFD::EXIF);
+        let entry = content.insert_value(Tag::FNUMBER, Value::URational(vec![Rational(28, 10)]))?;
+        assert_eq!(entry.format_value(TextFormat::Raw)?, "28/10");
+        assert_eq!(entry.format_value(TextFormat::Readable)?, "f/2.8");
+        assert_eq!(entry.format_value(TextFormat::ReadableWithoutUnits)?, "2.8");
+        let exposures = [(Rational(1, 250), "1/250"), (Rational(2, 1), "2")];
+        for (exposure, text) in exposures {
+            let entry =
+                content.insert_value(Tag::EXPOSURE_TIME, Value::URational(vec![exposure]))?;
+            assert_eq!(entry.format_value(TextFormat::ReadableWithoutUnits)?, text);
+        }
+        let entry = content.insert_value(
+            Tag::EXPOSURE_BIAS_VALUE,
+            Value::IRational(vec![Rational(-2, 3)]),
+        )?;
+        assert_eq!(
+            entry.format_value(TextFormat::ReadableWithoutUnits)?,
+            "-0.67"
+        );
+        // long values are not truncated
+        let artist = "A".repeat(1000);
+        let mut content = data.content_mut(IFD::Image);
+        let entry = content.insert_value(Tag::ARTIST, Value::Text(artist.clone()))?;
+        assert_eq!(entry.text_value()?, artist);
+        assert_eq!(
+            entry.format_value(TextFormat::ReadableWithoutUnits)?,
+            artist
+        );
+        assert_eq!(Value::U16(vec![2, 0, 0]).to_string(), "2, 0, 0");
+        assert_eq!(Value::Undefined(vec![0x30, 0x32]).to_string(), "30 32");
+        Ok(())
+    }
+
+    #[test]
+    fn test_orientation() -> Result<(), Error> {
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        assert_eq!(data.orientation(), Some(Orientation::TopRight));
+        data.set_orientation(Orientation::RightTop)?;
+        let copy = Data::from_bytes(&data.to_bytes())?;
+        assert_eq!(copy.orientation(), Some(Orientation::RightTop));
+        assert!(Orientation::try_from(9).is_err());
+
+        let all = (1..=8)
+            .map(Orientation::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        for orientation in all.iter().copied() {
+            let (mirrored, rotation) = (orientation.is_mirrored(), orientation.rotation());
+            assert_eq!(
+                Orientation::from_transform(mirrored, rotation),
+                Some(orientation)
+            );
+            assert_eq!(
+                orientation.then(orientation.inverse()),
+                Orientation::TopLeft
+            );
+            assert_eq!(
+                orientation.inverse().then(orientation),
+                Orientation::TopLeft
+            );
+        }
+        assert_eq!(Orientation::RightTop.rotation(), 90);
+        assert_eq!(Orientation::LeftBottom.rotation(), 270);
+        assert!(Orientation::LeftTop.is_mirrored() && Orientation::LeftTop.swaps_dimensions());
+        // two quarter turns make a half turn
+        assert_eq!(
+            Orientation::RightTop.then(Orientation::RightTop),
+            Orientation::BottomRight
+        );
+        // mirroring and then rotating by a quarter turn transposes the image
+        assert_eq!(
+            Orientation::TopRight.then(Orientation::LeftBottom),
+            Orientation::LeftTop
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_gps() -> Result<(), Error> {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        assert_eq!(data.gps(), None);
+        let gps = GpsInfo {
+            latitude: Some(-33.856784),
+            longitude: Some(151.215297),
+            altitude: Some(-12.5),
+            // 2024-02-29 13:45:30.250 UTC
+            timestamp: Some(UNIX_EPOCH + Duration::from_millis(1709214330250)),
+            map_datum: Some("WGS-84".into()),
+        };
+        data.set_gps(&gps)?;
+        let copy = Data::from_bytes(&data.to_bytes())?;
+        let byte_order = copy.byte_order()?;
+        let value = |tag| copy.get(IFD::GPS, tag).unwrap().value(byte_order).unwrap();
+        assert_eq!(value(Tag::GPS_VERSION_ID), Value::U8(vec![2, 2, 0, 0]));
+        assert_eq!(value(Tag::GPS_LATITUDE_REF), Value::Text("S".into()));
+        assert_eq!(
+            value(Tag::GPS_LATITUDE),
+            Value::URational(vec![
+                Rational(33, 1),
+                Rational(51, 1),
+                Rational(244224, 10000)
+            ])
+        );
+        assert_eq!(value(Tag::GPS_ALTITUDE_REF), Value::U8(vec![1]));
+        assert_eq!(value(Tag::GPS_DATE_STAMP), Value::Text("2024:02:29".into()));
+
+        let read = copy.gps().unwrap();
+        assert!((read.latitude.unwrap() - gps.latitude.unwrap()).abs() < 1e-7);
+        assert!((read.longitude.unwrap() - gps.longitude.unwrap()).abs() < 1e-7);
+        assert_eq!(read.altitude, gps.altitude);
+        assert_eq!(read.timestamp, gps.timestamp);
+        assert_eq!(read.map_datum, gps.map_datum);
+
+        // fields that are not set are removed
+        data.set_gps(&GpsInfo {
+            latitude: Some(45.0),
+            ..GpsInfo::default()
+        })?;
+        let read = data.gps().unwrap();
+        assert_eq!(read.latitude, Some(45.0));
+        assert_eq!(read.longitude, None);
+        assert!(data.get(IFD::GPS, Tag::GPS_MAP_DATUM).is_none());
+        let invalid = GpsInfo {
+            latitude: Some(91.0),
+            ..GpsInfo::default()
+        };
+        assert!(matches!(data.set_gps(&invalid), Err(Error::InvalidGps)));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn test_date_time() -> Result<(), Error> {
+        use chrono::{FixedOffset, NaiveDate, TimeZone, Timelike};
+
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        let naive = NaiveDate::from_ymd_opt(2016, 9, 5)
+            .unwrap()
+            .and_hms_milli_opt(14, 30, 15, 120)
+            .unwrap();
+        data.set_date_time(DateTimeKind::Modified, naive)?;
+        let offset = FixedOffset::west_opt(7 * 3600 + 30 * 60).unwrap();
+        let original = offset.from_local_datetime(&naive).unwrap();
+        data.set_date_time(DateTimeKind::Original, original)?;
+
+        let copy = Data::from_bytes(&data.to_bytes())?;
+        let text = |ifd, tag| copy.get(ifd, tag).map(|e| e.text_value().unwrap());
+        assert_eq!(
+            text(IFD::Image, Tag::DATE_TIME).as_deref(),
+            Some("2016:09:05 14:30:15")
+        );
+        assert_eq!(text(IFD::EXIF, Tag::SUB_SEC_TIME).as_deref(), Some("12"));
+        assert_eq!(text(IFD::EXIF, Tag::OFFSET_TIME), None);
+        assert_eq!(
+            text(IFD::EXIF, Tag::OFFSET_TIME_ORIGINAL).as_deref(),
+            Some("-07:30")
+        );
+        assert_eq!(
+            copy.date_time(DateTimeKind::Modified),
+            Some(ExifDateTime::Naive(naive))
+        );
+        assert_eq!(
+            copy.date_time(DateTimeKind::Original),
+            Some(ExifDateTime::Offset(original))
+        );
+
+        // writing a whole second without an offset removes the other tags
+        data.set_date_time(DateTimeKind::Original, naive.with_nanosecond(0).unwrap())?;
+        assert!(data.get(IFD::EXIF, Tag::SUB_SEC_TIME_ORIGINAL).is_none());
+        assert!(data.get(IFD::EXIF, Tag::OFFSET_TIME_ORIGINAL).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_camera_settings() -> Result<(), Error> {
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        let mut image = data.content_mut(IFD::Image);
+        image.insert_value(Tag::MAKE, Value::Text("Canon".into()))?;
+        image.insert_value(Tag::MODEL, Value::Text("Canon EOS 5D".into()))?;
+        let mut exif = data.content_mut(IFD::EXIF);
+        exif.insert_value(Tag::EXPOSURE_TIME, Value::URational(vec![Rational(1, 250)]))?;
+        exif.insert_value(Tag::FNUMBER, Value::URational(vec![Rational(28, 10)]))?;
+        exif.insert_value(Tag::ISO_SPEED_RATINGS, Value::U16(vec![400]))?;
+        exif.insert_value(Tag::FOCAL_LENGTH, Value::URational(vec![Rational(50, 1)]))?;
+        exif.insert_value(
+            Tag::EXPOSURE_BIAS_VALUE,
+            Value::IRational(vec![Rational(-2, 3)]),
+        )?;
+        exif.insert_value(Tag::FLASH, Value::U16(vec![0x19]))?;
+        exif.insert_value(Tag::METERING_MODE, Value::U16(vec![5]))?;
+        exif.insert_value(Tag::LENS_MODEL, Value::Text("EF50mm f/1.8".into()))?;
+
+        let settings = data.camera_settings();
+        assert_eq!(settings.make.as_deref(), Some("Canon"));
+        assert_eq!(settings.model.as_deref(), Some("Canon EOS 5D"));
+        assert_eq!(settings.iso, Some(400));
+        assert_eq!(settings.metering_mode, Some(MeteringMode::Pattern));
+        assert_eq!(settings.lens.as_deref(), Some("EF50mm f/1.8"));
+        let flash = settings.flash.unwrap();
+        assert!(flash.fired() && flash.is_present() && !flash.red_eye_reduction());
+        assert_eq!(flash.mode(), FlashMode::Auto);
+        assert_eq!(settings.exposure_time_text().as_deref(), Some("1/250 s"));
+        assert_eq!(settings.f_number_text().as_deref(), Some("f/2.8"));
+        assert_eq!(settings.focal_length_text().as_deref(), Some("50 mm"));
+        assert_eq!(settings.exposure_bias_text().as_deref(), Some("-0.7 EV"));
+        assert_eq!(settings.iso_text().as_deref(), Some("ISO 400"));
+        let long_exposure = CameraSettings {
+            exposure_time: Some(2.5),
+            ..CameraSettings::default()
+        };
+        assert_eq!(long_exposure.exposure_time_text().as_deref(), Some("2.5 s"));
+        assert_eq!(long_exposure.f_number_text(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_exposure_time_text() {
+        let text = |seconds| {
+            let settings = CameraSettings {
+                exposure_time: Some(seconds),
+                ..CameraSettings::default()
+            };
+            settings.exposure_time_text()
+        };
+        assert_eq!(text(0.8).as_deref(), Some("0.8 s"));
+        assert_eq!(text(0.5).as_deref(), Some("1/2 s"));
+        assert_eq!(text(0.3).as_deref(), Some("1/3 s"));
+        assert_eq!(text(1.0 / 3.0).as_deref(), Some("1/3 s"));
+        assert_eq!(text(1.0).as_deref(), Some("1 s"));
+        assert_eq!(text(0.0), None);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() -> Result<(), Error> {
+        let data = Data::open("tests/fixtures/f2t.jpg")?;
+        let json = serde_json::to_value(&data).unwrap();
+        let image = json["Image"].as_array().unwrap();
+        let orientation = image.iter().find(|entry| entry["tag"] == 0x0112).unwrap();
+        assert_eq!(orientation["name"], "Orientation");
+        assert_eq!(orientation["data_type"], "U16");
+        assert_eq!(orientation["value"], serde_json::json!({ "U16": [2] }));
+        assert!(json.get("GPS").is_none());
+
+        let value = Value::URational(vec![Rational(28, 10)]);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#"{"URational":[[28,10]]}"#);
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+        assert_eq!(serde_json::from_str::<IFD>(r#""EXIF""#).unwrap(), IFD::EXIF);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_exiftool_json() -> Result<(), Error> {
+        use serde_json::json;
+
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        let mut exif = data.content_mut(IFD::EXIF);
+        exif.insert_value(Tag::EXPOSURE_TIME, Value::URational(vec![Rational(1, 250)]))?;
+        exif.insert_value(Tag::ISO_SPEED_RATINGS, Value::U16(vec![400]))?;
+        let numeric = data.to_exiftool_json(JsonMode::Numeric);
+        assert_eq!(numeric["IFD0:Orientation"], json!(2));
+        assert_eq!(numeric["ExifIFD:ExposureTime"], json!(0.004));
+        assert_eq!(numeric["ExifIFD:ISO"], json!(400));
+        assert!(numeric.contains_key("ExifIFD:ExifVersion"));
+        let printable = data.to_exiftool_json(JsonMode::Printable);
+        assert_eq!(printable["IFD0:Orientation"], json!("Top-right"));
+        assert_eq!(printable["ExifIFD:ISO"], json!(400));
+
+        // round trip through a JSON file
+        let text = serde_json::to_string(&vec![numeric]).unwrap();
+        let mut copy = Data::from_bytes(&data.to_bytes())?;
+        copy.content_mut(IFD::EXIF).remove(Tag::EXPOSURE_TIME);
+        copy.apply_exiftool_json(&serde_json::from_str(&text).unwrap())?;
+        let byte_order = copy.byte_order()?;
+        let value = |tag| copy.get(IFD::EXIF, tag).unwrap().value(byte_order).unwrap();
+        assert_eq!(
+            value(Tag::EXPOSURE_TIME),
+            Value::URational(vec![Rational(1, 250)])
+        );
+        assert_eq!(value(Tag::ISO_SPEED_RATINGS), Value::U16(vec![400]));
+
+        copy.apply_exiftool_json(&json!({
+            "SourceFile": "f2t.jpg",
+            "IFD0:Orientation": 6,
+            "IFD0:Artist": "Jane Doe",
+            "GPS:GPSVersionID": "2 2 0 0",
+            "ExifIFD:ISO": null,
+        }))?;
+        assert_eq!(copy.orientation(), Some(Orientation::RightTop));
+        assert!(copy.get(IFD::Image, Tag::ARTIST).is_some());
+        assert_eq!(
+            copy.get(IFD::GPS, Tag::GPS_VERSION_ID)
+                .unwrap()
+                .value(byte_order)?,
+            Value::U8(vec![2, 2, 0, 0])
+        );
+        assert!(copy.get(IFD::EXIF, Tag::ISO_SPEED_RATINGS).is_none());
+        let result = copy.apply_exiftool_json(&json!({ "IFD0:Orientation": "sideways" }));
+        assert!(matches!(result, Err(Error::InvalidJson(key)) if key == "IFD0:Orientation"));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_exiftool_json_fixture() -> Result<(), Error> {
+        // in the style of `exiftool -j -G1 -n` for a camera JPEG
+        let fixture: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string("tests/fixtures/exiftool.json")?)
+                .unwrap();
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        data.take_diagnostics();
+        data.apply_exiftool_json(&fixture)?;
+        let diagnostics = data.take_diagnostics();
+        assert!(diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.domain() == "ExifJson"
+                && diagnostic.message().contains("ExifIFD:AmbientTemperature")));
+        assert!(data
+            .get(IFD::Thumbnail, Tag::JPEG_INTERCHANGE_FORMAT)
+            .is_none());
+
+        let byte_order = data.byte_order()?;
+        let value = |ifd, tag| data.get(ifd, tag).unwrap().value(byte_order).unwrap();
+        assert_eq!(data.orientation(), Some(Orientation::TopLeft));
+        assert_eq!(
+            value(IFD::EXIF, Tag::SUB_SEC_TIME_ORIGINAL),
+            Value::Text("52".to_string())
+        );
+        assert_eq!(
+            value(IFD::EXIF, Tag::SHUTTER_SPEED_VALUE),
+            Value::IRational(vec![Rational(8, 1)])
+        );
+        assert_eq!(
+            value(IFD::EXIF, Tag::APERTURE_VALUE),
+            Value::URational(vec![Rational(5, 1)])
+        );
+        assert_eq!(
+            value(IFD::EXIF, Tag::EXPOSURE_BIAS_VALUE),
+            Value::IRational(vec![Rational(-1, 3)])
+        );
+        assert_eq!(
+            value(IFD::EXIF, Tag::COMPONENTS_CONFIGURATION),
+            Value::Undefined(vec![1, 2, 3, 0])
+        );
+        assert_eq!(
+            value(IFD::EXIF, Tag::USER_COMMENT),
+            Value::Undefined(b"ASCII\0\0\0".to_vec())
+        );
+        assert_eq!(
+            value(IFD::GPS, Tag::GPS_TIME_STAMP),
+            Value::URational(vec![Rational(2, 1), Rational(30, 1), Rational(91, 2)])
+        );
+        let gps = data.gps().unwrap();
+        assert!((gps.latitude.unwrap() + 33.8568).abs() < 1e-9);
+        assert!((gps.longitude.unwrap() - 151.2153).abs() < 1e-9);
+
+        // written back the way exiftool names and writes the values
+        let numeric = data.to_exiftool_json(JsonMode::Numeric);
+        for key in [
+            "IFD0:ModifyDate",
+            "ExifIFD:ISO",
+            "ExifIFD:SubSecTime",
+            "ExifIFD:SubSecTimeOriginal",
+            "ExifIFD:ComponentsConfiguration",
+            "ExifIFD:UserComment",
+            "ExifIFD:ExifImageHeight",
+            "ExifIFD:LensInfo",
+            "GPS:GPSTimeStamp",
+            "GPS:GPSLatitudeRef",
+            "GPS:GPSLatitude",
+            "GPS:GPSLongitude",
+            "Composite:GPSLatitude",
+            "Composite:GPSLongitude",
+        ] {
+            assert_eq!(numeric[key], fixture[0][key], "{}", key);
+        }
+        let number = |key: &str| numeric[key].as_f64().unwrap();
+        assert!((number("ExifIFD:ShutterSpeedValue") - 0.00390625).abs() < 1e-12);
+        assert!((number("ExifIFD:ApertureValue") - 5.65685424949238).abs() < 1e-12);
+        assert!(!numeric.contains_key("IFD1:ThumbnailOffset"));
+
+        // nothing is applied if a value is invalid
+        let result = data.apply_exiftool_json(&serde_json::json!({
+            "IFD0:Artist": "Jane Doe",
+            "IFD0:Orientation": "sideways",
+        }));
+        assert!(matches!(result, Err(Error::InvalidJson(key)) if key == "IFD0:Orientation"));
+        assert_eq!(
+            data.get(IFD::Image, Tag::ARTIST)
+                .unwrap()
+                .value(byte_order)?,
+            Value::Text(String::new())
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "xmp")]
+    fn test_xmp() -> Result<(), Error> {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        let mut exif = data.content_mut(IFD::EXIF);
+        exif.insert_value(Tag::EXPOSURE_TIME, Value::URational(vec![Rational(1, 250)]))?;
+        // fired in auto mode
+        exif.insert_value(Tag::FLASH, Value::U16(vec![0x19]))?;
+        exif.insert_value(
+            Tag::DATE_TIME_ORIGINAL,
+            Value::Text("2024:02:29 13:45:30".into()),
+        )?;
+        exif.insert_value(Tag::SUB_SEC_TIME_ORIGINAL, Value::Text("25".into()))?;
+        exif.insert_value(Tag::OFFSET_TIME_ORIGINAL, Value::Text("+01:00".into()))?;
+        data.content_mut(IFD::Image)
+            .insert_value(Tag::ARTIST, Value::Text("Jane Doe; John Roe".into()))?;
+        data.set_gps(&GpsInfo {
+            latitude: Some(-33.856784),
+            longitude: Some(151.215297),
+            timestamp: Some(UNIX_EPOCH + Duration::from_millis(1709214330250)),
+            ..GpsInfo::default()
+        })?;
+        let packet = data.to_xmp();
+        for expected in [
+            "<tiff:Orientation>2</tiff:Orientation>",
+            "<exif:ExposureTime>1/250</exif:ExposureTime>",
+            "<exif:DateTimeOriginal>2024-02-29T13:45:30.25+01:00</exif:DateTimeOriginal>",
+            "<exif:GPSLatitude>33,51.407040S</exif:GPSLatitude>",
+            "<exif:GPSTimeStamp>2024-02-29T13:45:30.25Z</exif:GPSTimeStamp>",
+            "<exif:Fired>True</exif:Fired>",
+            "<exif:Mode>3</exif:Mode>",
+            "<rdf:li>John Roe</rdf:li>",
+        ] {
+            assert!(packet.contains(expected), "{} not in {}", expected, packet);
+        }
+
+        // round trip through the packet
+        let mut copy = Data::from_bytes(&data.to_bytes())?;
+        let tags = [
+            (IFD::Image, Tag::ARTIST),
+            (IFD::EXIF, Tag::EXPOSURE_TIME),
+            (IFD::EXIF, Tag::FLASH),
+            (IFD::EXIF, Tag::DATE_TIME_ORIGINAL),
+            (IFD::EXIF, Tag::SUB_SEC_TIME_ORIGINAL),
+            (IFD::EXIF, Tag::OFFSET_TIME_ORIGINAL),
+            (IFD::GPS, Tag::GPS_LATITUDE_REF),
+            (IFD::GPS, Tag::GPS_LATITUDE),
+            (IFD::GPS, Tag::GPS_DATE_STAMP),
+            (IFD::GPS, Tag::GPS_TIME_STAMP),
+        ];
+        for (ifd, tag) in tags {
+            copy.content_mut(ifd).remove(tag);
+        }
+        copy.apply_xmp(&packet)?;
+        let byte_order = data.byte_order()?;
+        for (ifd, tag) in tags {
+            assert_eq!(
+                copy.get(ifd, tag).unwrap().value(byte_order)?,
+                data.get(ifd, tag).unwrap().value(byte_order)?
+            );
+        }
+
+        // properties may also be attributes, and arrays may hold alternatives
+        copy.apply_xmp(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+             <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+              <rdf:Description rdf:about=""
+                xmlns:tiff="http://ns.adobe.com/tiff/1.0/"
+                xmlns:exif="http://ns.adobe.com/exif/1.0/"
+                xmlns:dc="http://purl.org/dc/elements/1.1/"
+                tiff:Orientation="6" exif:FNumber="28/10" exif:GPSLongitude="2,17.4W">
+               <dc:description>
+                <rdf:Alt>
+                 <rdf:li xml:lang="fr">Le port</rdf:li>
+                 <rdf:li xml:lang="x-default">The harbor</rdf:li>
+                </rdf:Alt>
+               </dc:description>
+               <exif:ISOSpeedRatings><rdf:Seq><rdf:li>800</rdf:li></rdf:Seq></exif:ISOSpeedRatings>
+              </rdf:Description>
+             </rdf:RDF>
+            </x:xmpmeta>"#,
+        )?;
+        let value = |ifd, tag| copy.get(ifd, tag).unwrap().value(byte_order).unwrap();
+        assert_eq!(copy.orientation(), Some(Orientation::RightTop));
+        assert_eq!(
+            value(IFD::EXIF, Tag::FNUMBER),
+            Value::URational(vec![Rational(28, 10)])
+        );
+        assert_eq!(
+            value(IFD::Image, Tag::IMAGE_DESCRIPTION),
+            Value::Text("The harbor".into())
+        );
+        assert_eq!(
+            value(IFD::EXIF, Tag::ISO_SPEED_RATINGS),
+            Value::U16(vec![800])
+        );
+        assert_eq!(
+            value(IFD::GPS, Tag::GPS_LONGITUDE_REF),
+            Value::Text("W".into())
+        );
+        assert_eq!(
+            value(IFD::GPS, Tag::GPS_LONGITUDE),
+            Value::URational(vec![
+                Rational(2, 1),
+                Rational(17, 1),
+                Rational(240000, 10000)
+            ])
+        );
+
+        // nothing is applied if a property is invalid
+        let before = copy.to_bytes();
+        let result = copy.apply_xmp(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+             <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+              <rdf:Description rdf:about=""
+                xmlns:tiff="http://ns.adobe.com/tiff/1.0/"
+                xmlns:exif="http://ns.adobe.com/exif/1.0/"
+                tiff:Orientation="1" exif:FNumber="fast"/>
+             </rdf:RDF>
+            </x:xmpmeta>"#,
+        );
+        assert!(matches!(
+            result,
+            Err(Error::InvalidXmp(message))
+                if message == "cannot apply exif:FNumber \"fast\": expected rational numbers"
+        ));
+        assert_eq!(copy.to_bytes(), before);
+        assert_eq!(copy.orientation(), Some(Orientation::RightTop));
+
+        assert!(matches!(
+            copy.apply_xmp("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">"),
+            Err(Error::InvalidXmp(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_value() -> io::Result<()> {
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        let byte_order = data.byte_order().unwrap();
+        let mut entry = data.get_mut(IFD::Image, Tag::ORIENTATION).unwrap();
+        assert!(entry
+            .set_value(Value::Text("upright".into()), byte_order)
+            .is_err());
+        entry.set_value(Value::U16(vec![6]), byte_order).unwrap();
+        assert_eq!(entry.components(), 1);
+        assert_eq!(entry.raw_data(), &[6, 0]);
+        // the new value survives a round trip through the raw EXIF block
+        let copy = Data::from_bytes(&data.to_bytes())?;
+        let mut found = false;
+        for content in copy.contents() {
             for entry in content.entries() {
-                // Orientation is 274
-                if entry.tag().code() == 274 {
+                if entry.tag() == Tag::ORIENTATION {
                     match entry.value(byte_order).unwrap() {
-                        Value::U16(v) => println!("v: {:?}", v),
+                        Value::U16(v) => assert_eq!(v, vec![6]),
                         _ => panic!("wrong type of value"),
                     }
+                    found = true;
                 }
             }
         }
+        assert!(found);
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_remove() -> io::Result<()> {
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        let mut content = data.content_mut(IFD::Image);
+        let len = content.len();
+        let entry = content
+            .insert_value(Tag::ARTIST, Value::Text("Jane Doe".into()))
+            .unwrap();
+        assert_eq!(entry.components(), 9);
+        assert_eq!(content.len(), len + 1);
+        // inserting an existing tag does not add another entry
+        content.insert(Tag::ARTIST).unwrap();
+        assert_eq!(content.len(), len + 1);
+        // Orientation does not accept text
+        assert!(content
+            .insert_value(Tag::ORIENTATION, Value::Text("upright".into()))
+            .is_err());
+        assert!(content.remove(Tag::ORIENTATION));
+        assert!(!content.remove(Tag::ORIENTATION));
+        assert_eq!(content.len(), len);
+        let copy = Data::from_bytes(&data.to_bytes())?;
+        let codes: Vec<u32> = copy
+            .contents()
+            .flat_map(|c| c.entries().map(|e| e.tag().code()).collect::<Vec<_>>())
+            .collect();
+        assert!(codes.contains(&Tag::ARTIST.code()));
+        assert!(!codes.contains(&Tag::ORIENTATION.code()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_without_default() -> Result<(), Error> {
+        let mut data = Data::open("tests/fixtures/f2t.jpg")?;
+        // libexif has no default value for these tags, so any type is accepted
+        let mut content = data.content_mut(IFD::EXIF);
+        content.insert_value(Tag::LENS_MODEL, Value::Text("EF 50mm".into()))?;
+        content.insert_value(Tag::OFFSET_TIME, Value::Text("+02:00".into()))?;
+        content.insert_value(Tag::from(0xc000), Value::U32(vec![1]))?;
+        assert!(content
+            .insert_value(Tag::EXPOSURE_TIME, Value::Text("1/250".into()))
+            .is_err());
         Ok(())
     }
 }
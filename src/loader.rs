@@ -2,42 +2,208 @@
 // Copyright (c) 2016 David Cuddeback
 //
 use crate::data::Data;
-use crate::internal::*;
+use crate::diagnostic::{Diagnostic, Log};
+use crate::mem::{self as exif_mem, MemoryBudget};
 use libc::c_uint;
 use libexif_sys::*;
+use std::io;
+use std::ptr;
+use std::slice;
 
+/// Incremental reader of EXIF data from a JPEG stream.
+///
+/// A `Loader` is fed the bytes of an image through its
+/// [`Write`](https://doc.rust-lang.org/std/io/trait.Write.html) implementation
+/// and buffers the EXIF segment as it goes by. Once
+/// [`needs_more()`](#method.needs_more) returns `false` the remaining bytes of
+/// the image are of no interest and the EXIF data can be retrieved with
+/// [`data()`](#method.data).
+///
+/// ```no_run
+/// # use std::io::{self, Read, Write};
+/// # fn load<R: Read>(mut reader: R) -> io::Result<Option<libexif::Data>> {
+/// let mut loader = libexif::Loader::new();
+/// let mut buffer = [0u8; 4096];
+/// while loader.needs_more() {
+///     let len = reader.read(&mut buffer)?;
+///     if len == 0 {
+///         break;
+///     }
+///     loader.write_all(&buffer[..len])?;
+/// }
+/// Ok(loader.data())
+/// # }
+/// ```
 pub struct Loader {
     inner: *mut ExifLoader,
+    /// The allocator used by `inner`, if not the default one.
+    mem: *mut ExifMem,
+    budget: Option<MemoryBudget>,
+    limit_exceeded: bool,
+    needs_more: bool,
+    log: Log,
 }
 
+// The loader's libexif objects are referenced only by this value; see the
+// comment on Data.
+unsafe impl Send for Loader {}
+
 impl Drop for Loader {
     fn drop(&mut self) {
         unsafe {
             exif_loader_unref(self.inner);
+            if !self.mem.is_null() {
+                exif_mem_unref(self.mem);
+            }
         }
     }
 }
 
+impl Default for Loader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Loader {
+    /// Construct a new loader that is ready to receive data.
     pub fn new() -> Self {
         let ptr = unsafe { exif_loader_new() };
 
         assert!(!ptr.is_null());
 
-        Loader { inner: ptr }
+        Loader::from_parts(ptr, ptr::null_mut(), None)
+    }
+
+    /// Construct a new loader that limits the resources used by libexif to
+    /// the given budget.
+    ///
+    /// If the budget is exceeded, the loader stops accepting data and
+    /// [`data()`](#method.data) returns `None`.
+    pub fn with_budget(budget: &MemoryBudget) -> Self {
+        let mem = exif_mem::new_budget_mem();
+        let ptr = unsafe { exif_loader_new_mem(mem) };
+
+        assert!(!ptr.is_null());
+
+        Loader::from_parts(ptr, mem, Some(budget.clone()))
     }
 
+    fn from_parts(ptr: *mut ExifLoader, mem: *mut ExifMem, budget: Option<MemoryBudget>) -> Self {
+        let log = Log::new();
+        unsafe {
+            exif_loader_log(ptr, log.as_ptr());
+        }
+
+        Loader {
+            inner: ptr,
+            mem,
+            budget,
+            limit_exceeded: false,
+            needs_more: true,
+            log,
+        }
+    }
+
+    /// Return `true` if the loader has not yet seen the complete EXIF data.
+    ///
+    /// Once this returns `false`, any further writes are ignored.
+    pub fn needs_more(&self) -> bool {
+        self.needs_more
+    }
+
+    /// Construct the EXIF data from the bytes buffered so far, if any.
+    ///
+    /// Returns `None` if the loader's memory budget was exceeded.
     pub fn data(&self) -> Option<Data> {
-        let ptr = unsafe { exif_loader_get_data(self.inner) };
+        self.load_data().ok().flatten()
+    }
+
+    pub(crate) fn load_data(&self) -> Result<Option<Data>, super::Error> {
+        if self.limit_exceeded {
+            return Err(super::Error::LimitExceeded);
+        }
+        let raw_data = self.raw_data();
+        if raw_data.is_empty() {
+            return Ok(None);
+        }
+        // unlike exif_loader_get_data(), give the data an allocator of its
+        // own so that it shares nothing with the loader
+        let mem = match self.budget {
+            Some(_) => exif_mem::new_budget_mem(),
+            None => unsafe { exif_mem_new_default() },
+        };
+        let scope = self.budget.as_ref().map(MemoryBudget::enter);
+        let ptr = unsafe { exif_data_new_mem(mem) };
+        if ptr.is_null() {
+            unsafe { exif_mem_unref(mem) };
+            return Err(match scope {
+                Some(_) => super::Error::LimitExceeded,
+                None => super::Error::OutOfMemory,
+            });
+        }
+        let mut data = Data::with_mem(ptr, mem);
+        data.load_raw(raw_data);
+        if let (Some(budget), Some(scope)) = (&self.budget, scope) {
+            data.check_budget(budget, scope.exceeded())?;
+        }
+        Ok(Some(data))
+    }
+
+    /// Return the raw payload of the EXIF segment buffered so far.
+    ///
+    /// For a JPEG stream this is the content of the APP1 segment, starting with
+    /// the `Exif\0\0` header.
+    pub fn raw_data(&self) -> &[u8] {
+        let mut buffer: *const u8 = ptr::null();
+        let mut size: c_uint = 0;
+        unsafe {
+            exif_loader_get_buf(self.inner, &mut buffer, &mut size);
+            if buffer.is_null() || size == 0 {
+                &[]
+            } else {
+                slice::from_raw_parts(buffer, size as usize)
+            }
+        }
+    }
+
+    /// Remove and return the messages libexif has reported while loading.
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.log.take()
+    }
+
+    /// Discard all buffered data so that the loader can be used again.
+    pub fn reset(&mut self) {
+        unsafe {
+            exif_loader_reset(self.inner);
+        }
+        self.limit_exceeded = false;
+        self.needs_more = true;
+    }
+}
 
-        if !ptr.is_null() {
-            Some(Data::from_libexif(ptr))
-        } else {
-            None
+impl io::Write for Loader {
+    /// Feed bytes to the loader.
+    ///
+    /// Bytes written after the loader has stopped looking for more data are
+    /// accepted and ignored.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(c_uint::MAX as usize);
+        if self.needs_more && len > 0 {
+            let scope = self.budget.as_ref().map(MemoryBudget::enter);
+            // libexif copies out of the buffer without modifying it
+            self.needs_more = unsafe {
+                exif_loader_write(self.inner, buf.as_ptr() as *mut u8, len as c_uint) != 0
+            };
+            if scope.is_some_and(|scope| scope.exceeded()) {
+                self.limit_exceeded = true;
+                self.needs_more = false;
+            }
         }
+        Ok(len)
     }
 
-    pub fn write_data(&mut self, data: &mut [u8]) -> bool {
-        unsafe { exif_loader_write(self.inner, data.as_mut_ptr(), data.len() as c_uint) != 0 }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
@@ -0,0 +1,683 @@
+use crate::bits::*;
+use crate::data::Data;
+use crate::entry::render_text;
+use crate::tag::Tag;
+use crate::tiff::{self, RawIfd};
+use crate::value::{Rational, Value};
+use libc::{c_char, c_uint};
+use libexif_sys::*;
+use std::ffi::CStr;
+use std::marker::PhantomData;
+
+/// Maker note data, decoded according to the vendor that recorded it.
+///
+/// Maker notes contain vendor-specific information recorded by the camera.
+/// libexif understands the maker notes of Canon, Olympus (including Nikon,
+/// Sanyo and Epson), Fuji, Pentax (including Casio) and Apple devices. The
+/// vendor is identified the same way libexif does it, and each variant offers
+/// typed accessors for the fields of that vendor's maker note.
+///
+/// Every variant also provides libexif's textual interpretation of the
+/// entries through [`entries()`](#method.entries).
+pub enum MakerNote<'a> {
+    /// Maker note recorded by a Canon camera.
+    Canon(CanonMakerNote<'a>),
+    /// Maker note in one of the Olympus formats, which are also used by
+    /// Nikon, Sanyo and Epson cameras.
+    Olympus(OlympusMakerNote<'a>),
+    /// Maker note recorded by a Fujifilm camera.
+    Fuji(FujiMakerNote<'a>),
+    /// Maker note in one of the Pentax formats, which are also used by Casio
+    /// cameras.
+    Pentax(PentaxMakerNote<'a>),
+    /// Maker note recorded by an Apple device.
+    Apple(AppleMakerNote<'a>),
+    /// Maker note interpreted by libexif that could not be attributed to a
+    /// vendor.
+    Unknown(GenericMakerNote<'a>),
+}
+
+impl<'a> MakerNote<'a> {
+    /// Interpret the maker note of the given data.
+    ///
+    /// `offset` is the position of the MakerNote entry's value in the TIFF
+    /// block the data was loaded from. Most maker notes locate their values
+    /// relative to that block, so offsets are rebased onto the entry's value.
+    pub(crate) fn new(data: &'a Data, inner: *mut ExifMnoteData, offset: usize) -> MakerNote<'a> {
+        let generic = GenericMakerNote::new(inner);
+        let make = data
+            .get(IFD::Image, Tag::MAKE)
+            .and_then(|entry| entry.text_value().ok())
+            .unwrap_or_default();
+        let (Some(entry), Ok(byte_order)) =
+            (data.get(IFD::EXIF, Tag::MAKER_NOTE), data.byte_order())
+        else {
+            return MakerNote::Unknown(generic);
+        };
+        let note = entry.raw_data();
+        // base of offsets relative to the TIFF block
+        let tiff_base = -(offset as isize);
+        let parse = |offset: usize, base: isize, byte_order: ByteOrder| {
+            RawIfd::parse(note, offset, base, byte_order).unwrap_or_default()
+        };
+
+        if note.starts_with(b"OLYMPUS\0") {
+            let byte_order = tiff::read_byte_order(note, 8).unwrap_or(byte_order);
+            MakerNote::Olympus(OlympusMakerNote {
+                note: generic,
+                variant: OlympusVariant::Olympus,
+                ifd: parse(12, 0, byte_order),
+            })
+        } else if note.starts_with(b"OLYMP\0")
+            || note.starts_with(b"SANYO\0")
+            || note.starts_with(b"EPSON\0")
+            || note.starts_with(b"Nikon\0\x01")
+        {
+            let variant = match &note[..5] {
+                b"SANYO" => OlympusVariant::Sanyo,
+                b"EPSON" => OlympusVariant::Epson,
+                b"Nikon" => OlympusVariant::Nikon,
+                _ => OlympusVariant::Olympus,
+            };
+            MakerNote::Olympus(OlympusMakerNote {
+                note: generic,
+                variant,
+                ifd: parse(8, tiff_base, byte_order),
+            })
+        } else if note.starts_with(b"Nikon\0\x02") {
+            // the maker note embeds a TIFF header of its own
+            let header = 10;
+            let ifd = tiff::read_byte_order(note, header)
+                .and_then(|byte_order| {
+                    let offset = tiff::read_u32(note, header + 4, byte_order)? as usize;
+                    RawIfd::parse(note, header + offset, header as isize, byte_order)
+                })
+                .unwrap_or_default();
+            MakerNote::Olympus(OlympusMakerNote {
+                note: generic,
+                variant: OlympusVariant::Nikon,
+                ifd,
+            })
+        } else if (make.starts_with("Nikon") || make.starts_with("NIKON"))
+            && note.starts_with(&[0x00, 0x1b])
+        {
+            MakerNote::Olympus(OlympusMakerNote {
+                note: generic,
+                variant: OlympusVariant::Nikon,
+                ifd: parse(0, tiff_base, byte_order),
+            })
+        } else if make == "Canon" {
+            MakerNote::Canon(CanonMakerNote {
+                note: generic,
+                ifd: parse(0, tiff_base, byte_order),
+            })
+        } else if note.starts_with(b"FUJIFILM") {
+            // Fuji maker notes are always little-endian, with offsets relative
+            // to the start of the maker note
+            let ifd = tiff::read_u32(note, 8, ByteOrder::LittleEndian)
+                .and_then(|offset| RawIfd::parse(note, offset as usize, 0, ByteOrder::LittleEndian))
+                .unwrap_or_default();
+            MakerNote::Fuji(FujiMakerNote { note: generic, ifd })
+        } else if note.starts_with(b"AOC\0") || note.starts_with(b"QVC\0") {
+            let variant = if note.starts_with(b"QVC\0") {
+                PentaxVariant::Casio
+            } else {
+                PentaxVariant::Pentax
+            };
+            let byte_order = tiff::read_byte_order(note, 4).unwrap_or(byte_order);
+            MakerNote::Pentax(PentaxMakerNote {
+                note: generic,
+                variant,
+                quality_tag: 0x0008,
+                ifd: parse(6, tiff_base, byte_order),
+            })
+        } else if note.starts_with(&[0x00, 0x1b]) {
+            // the original Pentax format has no header
+            MakerNote::Pentax(PentaxMakerNote {
+                note: generic,
+                variant: PentaxVariant::Pentax,
+                quality_tag: 0x0002,
+                ifd: parse(0, tiff_base, byte_order),
+            })
+        } else if note.starts_with(b"Apple iOS\0") {
+            let byte_order = tiff::read_byte_order(note, 12).unwrap_or(ByteOrder::BigEndian);
+            MakerNote::Apple(AppleMakerNote {
+                note: generic,
+                ifd: parse(14, 0, byte_order),
+            })
+        } else {
+            MakerNote::Unknown(generic)
+        }
+    }
+
+    /// Return libexif's generic view of the maker note.
+    pub fn generic(&self) -> &GenericMakerNote<'a> {
+        match self {
+            MakerNote::Canon(note) => &note.note,
+            MakerNote::Olympus(note) => &note.note,
+            MakerNote::Fuji(note) => &note.note,
+            MakerNote::Pentax(note) => &note.note,
+            MakerNote::Apple(note) => &note.note,
+            MakerNote::Unknown(note) => note,
+        }
+    }
+
+    /// Return the number of [entries](struct.MakerNoteEntry.html) in the
+    /// maker note.
+    pub fn len(&self) -> usize {
+        self.generic().len()
+    }
+
+    /// Return `true` if the maker note has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.generic().is_empty()
+    }
+
+    /// Iterate over the [entries](struct.MakerNoteEntry.html) in the maker
+    /// note.
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = MakerNoteEntry> + '_ {
+        self.generic().entries()
+    }
+}
+
+/// Maker note recorded by a Canon camera.
+pub struct CanonMakerNote<'a> {
+    note: GenericMakerNote<'a>,
+    ifd: RawIfd,
+}
+
+impl<'a> CanonMakerNote<'a> {
+    /// Return the raw value of the maker note tag with the given identifier.
+    pub fn value(&self, id: u16) -> Option<Value> {
+        self.ifd.get(id)?.value()
+    }
+
+    /// Return the CameraSettings array, indexed as documented by Canon.
+    pub fn camera_settings(&self) -> Option<Vec<u16>> {
+        match self.value(0x0001)? {
+            Value::U16(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Return the macro mode from the CameraSettings.
+    pub fn macro_mode(&self) -> Option<CanonMacroMode> {
+        match *self.camera_settings()?.get(1)? {
+            1 => Some(CanonMacroMode::Macro),
+            2 => Some(CanonMacroMode::Normal),
+            code => Some(CanonMacroMode::Other(code)),
+        }
+    }
+
+    /// Return the image quality from the CameraSettings.
+    pub fn quality(&self) -> Option<CanonQuality> {
+        match *self.camera_settings()?.get(3)? {
+            1 => Some(CanonQuality::Economy),
+            2 => Some(CanonQuality::Normal),
+            3 => Some(CanonQuality::Fine),
+            4 => Some(CanonQuality::Raw),
+            5 => Some(CanonQuality::Superfine),
+            code => Some(CanonQuality::Other(code)),
+        }
+    }
+
+    /// Return the image type, such as `"IMG:EOS 300D DIGITAL JPEG"`.
+    pub fn image_type(&self) -> Option<String> {
+        text(&self.ifd, 0x0006)
+    }
+
+    /// Return the camera firmware version.
+    pub fn firmware_version(&self) -> Option<String> {
+        text(&self.ifd, 0x0007)
+    }
+
+    /// Return the name of the camera owner.
+    pub fn owner_name(&self) -> Option<String> {
+        text(&self.ifd, 0x0009)
+    }
+
+    /// Return the camera serial number.
+    pub fn serial_number(&self) -> Option<u32> {
+        match self.value(0x000c)? {
+            Value::U32(values) => values.first().copied(),
+            _ => None,
+        }
+    }
+}
+
+/// Macro mode recorded in the Canon CameraSettings.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum CanonMacroMode {
+    Macro,
+    Normal,
+    /// A value not known to this library.
+    Other(u16),
+}
+
+/// Image quality recorded in the Canon CameraSettings.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum CanonQuality {
+    Economy,
+    Normal,
+    Fine,
+    Raw,
+    Superfine,
+    /// A value not known to this library.
+    Other(u16),
+}
+
+/// Maker note in one of the Olympus formats.
+pub struct OlympusMakerNote<'a> {
+    note: GenericMakerNote<'a>,
+    variant: OlympusVariant,
+    ifd: RawIfd,
+}
+
+impl<'a> OlympusMakerNote<'a> {
+    /// Return the vendor whose variant of the format is used.
+    pub fn variant(&self) -> OlympusVariant {
+        self.variant
+    }
+
+    /// Return the raw value of the maker note tag with the given identifier.
+    pub fn value(&self, id: u16) -> Option<Value> {
+        self.ifd.get(id)?.value()
+    }
+
+    /// Return the image quality. Not available in Nikon maker notes.
+    pub fn quality(&self) -> Option<OlympusQuality> {
+        match self.olympus_u16(0x0201)? {
+            1 => Some(OlympusQuality::Sq),
+            2 => Some(OlympusQuality::Hq),
+            3 => Some(OlympusQuality::Shq),
+            4 => Some(OlympusQuality::Raw),
+            code => Some(OlympusQuality::Other(code)),
+        }
+    }
+
+    /// Return the macro mode. Not available in Nikon maker notes.
+    pub fn macro_mode(&self) -> Option<OlympusMacroMode> {
+        match self.olympus_u16(0x0202)? {
+            0 => Some(OlympusMacroMode::Normal),
+            1 => Some(OlympusMacroMode::Macro),
+            2 => Some(OlympusMacroMode::SuperMacro),
+            code => Some(OlympusMacroMode::Other(code)),
+        }
+    }
+
+    /// Return the digital zoom ratio. Not available in Nikon maker notes.
+    pub fn digital_zoom(&self) -> Option<Rational<u32>> {
+        if self.variant == OlympusVariant::Nikon {
+            return None;
+        }
+        match self.value(0x0204)? {
+            Value::URational(values) => values.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// Return the firmware version. Not available in Nikon maker notes.
+    pub fn software_release(&self) -> Option<String> {
+        if self.variant == OlympusVariant::Nikon {
+            return None;
+        }
+        text(&self.ifd, 0x0207)
+    }
+
+    /// Return the image quality, such as `"FINE"`. Only available in Nikon
+    /// maker notes.
+    pub fn nikon_quality(&self) -> Option<String> {
+        if self.variant != OlympusVariant::Nikon {
+            return None;
+        }
+        text(&self.ifd, 0x0004)
+    }
+
+    // Nikon uses the same tags for other purposes
+    fn olympus_u16(&self, id: u16) -> Option<u16> {
+        if self.variant == OlympusVariant::Nikon {
+            None
+        } else {
+            first_u16(&self.ifd, id)
+        }
+    }
+}
+
+/// Vendors using the Olympus maker note format.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum OlympusVariant {
+    Olympus,
+    Nikon,
+    Sanyo,
+    Epson,
+}
+
+/// Image quality recorded in an Olympus maker note.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum OlympusQuality {
+    /// Standard quality.
+    Sq,
+    /// High quality.
+    Hq,
+    /// Super high quality.
+    Shq,
+    Raw,
+    /// A value not known to this library.
+    Other(u16),
+}
+
+/// Macro mode recorded in an Olympus maker note.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum OlympusMacroMode {
+    Normal,
+    Macro,
+    SuperMacro,
+    /// A value not known to this library.
+    Other(u16),
+}
+
+/// Maker note recorded by a Fujifilm camera.
+pub struct FujiMakerNote<'a> {
+    note: GenericMakerNote<'a>,
+    ifd: RawIfd,
+}
+
+impl<'a> FujiMakerNote<'a> {
+    /// Return the raw value of the maker note tag with the given identifier.
+    pub fn value(&self, id: u16) -> Option<Value> {
+        self.ifd.get(id)?.value()
+    }
+
+    /// Return the camera serial number.
+    pub fn serial_number(&self) -> Option<String> {
+        text(&self.ifd, 0x0010)
+    }
+
+    /// Return the image quality, such as `"NORMAL"` or `"FINE"`.
+    pub fn quality(&self) -> Option<String> {
+        text(&self.ifd, 0x1000)
+    }
+
+    /// Return `true` if macro mode was enabled.
+    pub fn macro_mode(&self) -> Option<bool> {
+        first_u16(&self.ifd, 0x1020).map(|value| value != 0)
+    }
+
+    /// Return the film simulation.
+    pub fn film_simulation(&self) -> Option<FilmSimulation> {
+        Some(match first_u16(&self.ifd, 0x1401)? {
+            0x000 => FilmSimulation::Provia,
+            0x100 => FilmSimulation::StudioPortrait,
+            0x110 => FilmSimulation::StudioPortraitEnhancedSaturation,
+            0x120 => FilmSimulation::Astia,
+            0x130 => FilmSimulation::StudioPortraitIncreasedSharpness,
+            0x200 => FilmSimulation::Fujichrome,
+            0x300 => FilmSimulation::StudioPortraitEx,
+            0x400 => FilmSimulation::Velvia,
+            0x500 => FilmSimulation::ProNegStd,
+            0x501 => FilmSimulation::ProNegHi,
+            0x600 => FilmSimulation::ClassicChrome,
+            0x700 => FilmSimulation::Eterna,
+            0x800 => FilmSimulation::ClassicNegative,
+            0x900 => FilmSimulation::EternaBleachBypass,
+            0xa00 => FilmSimulation::NostalgicNegative,
+            0xb00 => FilmSimulation::RealaAce,
+            code => FilmSimulation::Other(code),
+        })
+    }
+}
+
+/// Film simulation recorded in a Fuji maker note (the FilmMode tag).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum FilmSimulation {
+    /// Standard (F0).
+    Provia,
+    /// F1.
+    StudioPortrait,
+    /// F1a.
+    StudioPortraitEnhancedSaturation,
+    /// Soft (F1b).
+    Astia,
+    /// F1c.
+    StudioPortraitIncreasedSharpness,
+    /// F2.
+    Fujichrome,
+    /// F3.
+    StudioPortraitEx,
+    /// Vivid (F4).
+    Velvia,
+    ProNegStd,
+    ProNegHi,
+    ClassicChrome,
+    Eterna,
+    ClassicNegative,
+    EternaBleachBypass,
+    NostalgicNegative,
+    RealaAce,
+    /// A value not known to this library.
+    Other(u16),
+}
+
+/// Maker note in one of the Pentax formats.
+pub struct PentaxMakerNote<'a> {
+    note: GenericMakerNote<'a>,
+    variant: PentaxVariant,
+    quality_tag: u16,
+    ifd: RawIfd,
+}
+
+impl<'a> PentaxMakerNote<'a> {
+    /// Return the vendor whose variant of the format is used.
+    pub fn variant(&self) -> PentaxVariant {
+        self.variant
+    }
+
+    /// Return the raw value of the maker note tag with the given identifier.
+    pub fn value(&self, id: u16) -> Option<Value> {
+        self.ifd.get(id)?.value()
+    }
+
+    /// Return the image quality. Not available in Casio maker notes.
+    pub fn quality(&self) -> Option<PentaxQuality> {
+        if self.variant == PentaxVariant::Casio {
+            return None;
+        }
+        Some(match first_u16(&self.ifd, self.quality_tag)? {
+            0 => PentaxQuality::Good,
+            1 => PentaxQuality::Better,
+            2 => PentaxQuality::Best,
+            3 => PentaxQuality::Tiff,
+            4 => PentaxQuality::Raw,
+            5 => PentaxQuality::Premium,
+            code => PentaxQuality::Other(code),
+        })
+    }
+}
+
+/// Vendors using the Pentax maker note format.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PentaxVariant {
+    Pentax,
+    Casio,
+}
+
+/// Image quality recorded in a Pentax maker note.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PentaxQuality {
+    Good,
+    Better,
+    Best,
+    Tiff,
+    Raw,
+    Premium,
+    /// A value not known to this library.
+    Other(u16),
+}
+
+/// Maker note recorded by an Apple device.
+pub struct AppleMakerNote<'a> {
+    note: GenericMakerNote<'a>,
+    ifd: RawIfd,
+}
+
+impl<'a> AppleMakerNote<'a> {
+    /// Return the raw value of the maker note tag with the given identifier.
+    pub fn value(&self, id: u16) -> Option<Value> {
+        self.ifd.get(id)?.value()
+    }
+
+    /// Return the identifier shared by the images of a burst.
+    pub fn burst_uuid(&self) -> Option<String> {
+        text(&self.ifd, 0x000b)
+    }
+
+    /// Return the identifier that pairs a Live Photo with its video.
+    pub fn content_identifier(&self) -> Option<String> {
+        text(&self.ifd, 0x0011)
+    }
+}
+
+fn first_u16(ifd: &RawIfd, id: u16) -> Option<u16> {
+    match ifd.get(id)?.value()? {
+        Value::U16(values) => values.first().copied(),
+        _ => None,
+    }
+}
+
+fn text(ifd: &RawIfd, id: u16) -> Option<String> {
+    match ifd.get(id)?.value()? {
+        Value::Text(text) => Some(text.trim_end().to_string()),
+        _ => None,
+    }
+}
+
+/// Maker note data, as interpreted by libexif.
+pub struct GenericMakerNote<'a> {
+    inner: *mut ExifMnoteData,
+    phantom: PhantomData<&'a ExifData>,
+}
+
+impl<'a> GenericMakerNote<'a> {
+    fn new(inner: *mut ExifMnoteData) -> GenericMakerNote<'a> {
+        GenericMakerNote {
+            inner,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Return the number of [entries](struct.MakerNoteEntry.html) in the
+    /// maker note.
+    pub fn len(&self) -> usize {
+        unsafe { exif_mnote_data_count(self.inner) as usize }
+    }
+
+    /// Return `true` if the maker note has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the [entries](struct.MakerNoteEntry.html) in the maker
+    /// note.
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = MakerNoteEntry> + '_ {
+        MakerNoteEntries {
+            maker_note: self,
+            index: 0,
+            count: self.len() as c_uint,
+        }
+    }
+}
+
+/// A single entry in a [`MakerNote`](enum.MakerNote.html).
+#[derive(Debug, Clone)]
+pub struct MakerNoteEntry {
+    id: u32,
+    name: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    value: Option<String>,
+}
+
+impl MakerNoteEntry {
+    /// Vendor-specific identifier of the entry.
+    ///
+    /// Several entries may share an identifier when libexif splits a single
+    /// maker note tag into multiple values.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The name of the entry, such as `"ImageType"`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The title of the entry, suitable for display.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// A verbose description of the entry.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// A textual representation of the entry's value, as formatted by
+    /// libexif.
+    ///
+    /// Like the text of [`Entry::format_value()`](struct.Entry.html#method.format_value),
+    /// it is limited to 1 MiB and truncated beyond that.
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+}
+
+struct MakerNoteEntries<'b> {
+    maker_note: &'b GenericMakerNote<'b>,
+    index: c_uint,
+    count: c_uint,
+}
+
+impl<'b> Iterator for MakerNoteEntries<'b> {
+    type Item = MakerNoteEntry;
+
+    fn next(&mut self) -> Option<MakerNoteEntry> {
+        if self.index < self.count {
+            let data = self.maker_note.inner;
+            let n = self.index;
+            self.index += 1;
+            let value = render_text(1024, |buffer, len| unsafe {
+                exif_mnote_data_get_value(data, n, buffer, len)
+            });
+            unsafe {
+                Some(MakerNoteEntry {
+                    id: exif_mnote_data_get_id(data, n),
+                    name: to_string(exif_mnote_data_get_name(data, n)),
+                    title: to_string(exif_mnote_data_get_title(data, n)),
+                    description: to_string(exif_mnote_data_get_description(data, n)),
+                    value: value.map(|value| String::from_utf8_lossy(&value).into_owned()),
+                })
+            }
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.count - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'b> ExactSizeIterator for MakerNoteEntries<'b> {
+    fn len(&self) -> usize {
+        (self.count - self.index) as usize
+    }
+}
+
+unsafe fn to_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
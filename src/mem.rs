@@ -0,0 +1,225 @@
+use libc::c_void;
+use libexif_sys::*;
+use std::cell::Cell;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Limits on the resources libexif may use while loading EXIF data.
+///
+/// Loading untrusted EXIF data can make libexif allocate large amounts of
+/// memory. A `MemoryBudget` caps the number of bytes libexif may hold at once
+/// and the number of entries the loaded data may contain. Allocations that
+/// would exceed the byte budget fail, and the loading functions that accept a
+/// budget, such as
+/// [`Data::from_bytes_with_budget()`](struct.Data.html#method.from_bytes_with_budget),
+/// then return [`Error::LimitExceeded`](enum.Error.html#variant.LimitExceeded).
+///
+/// Clones of a budget share their limits and counters, so a single budget
+/// can cap the memory used by several loads at once, across threads. Memory
+/// allocated by libexif while loading is charged to the budget until it is
+/// released, which includes the memory held by the resulting
+/// [`Data`](struct.Data.html). Allocations made while modifying the data
+/// afterwards are not charged.
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    inner: Arc<Budget>,
+}
+
+#[derive(Debug)]
+struct Budget {
+    max_bytes: usize,
+    max_entries: usize,
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl Budget {
+    /// Charge `size` bytes to the budget, returning `false` if that would
+    /// exceed it.
+    fn reserve(&self, size: usize) -> bool {
+        let mut current = self.current.load(Ordering::Relaxed);
+        loop {
+            let Some(next) = current.checked_add(size).filter(|n| *n <= self.max_bytes) else {
+                return false;
+            };
+            match self.current.compare_exchange_weak(
+                current,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    self.peak.fetch_max(next, Ordering::Relaxed);
+                    return true;
+                }
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    fn release(&self, size: usize) {
+        self.current.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+impl MemoryBudget {
+    /// Construct a budget allowing at most `max_bytes` bytes of memory and
+    /// `max_entries` entries (summed over all IFDs).
+    pub fn new(max_bytes: usize, max_entries: usize) -> Self {
+        MemoryBudget {
+            inner: Arc::new(Budget {
+                max_bytes,
+                max_entries,
+                current: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Return the maximum number of bytes that may be allocated at once.
+    pub fn max_bytes(&self) -> usize {
+        self.inner.max_bytes
+    }
+
+    /// Return the maximum number of entries the loaded data may contain.
+    pub fn max_entries(&self) -> usize {
+        self.inner.max_entries
+    }
+
+    /// Return the number of bytes currently charged to the budget.
+    pub fn current(&self) -> usize {
+        self.inner.current.load(Ordering::Relaxed)
+    }
+
+    /// Return the largest number of bytes that were charged to the budget at
+    /// any one time.
+    pub fn peak(&self) -> usize {
+        self.inner.peak.load(Ordering::Relaxed)
+    }
+
+    /// Charge the allocations libexif makes on this thread to the budget
+    /// until the returned scope is dropped.
+    pub(crate) fn enter(&self) -> Scope {
+        let previous = ACTIVE.with(|active| active.replace(Arc::as_ptr(&self.inner)));
+        let previous_exceeded = EXCEEDED.with(|exceeded| exceeded.replace(false));
+        Scope {
+            previous,
+            previous_exceeded,
+        }
+    }
+}
+
+/// Charges allocations to a budget while it is alive.
+pub(crate) struct Scope {
+    previous: *const Budget,
+    previous_exceeded: bool,
+}
+
+impl Scope {
+    /// Return `true` if an allocation failed because the budget was exhausted.
+    pub fn exceeded(&self) -> bool {
+        EXCEEDED.with(|exceeded| exceeded.get())
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| active.set(self.previous));
+        EXCEEDED.with(|exceeded| exceeded.set(self.previous_exceeded));
+    }
+}
+
+thread_local! {
+    /// The budget charged for allocations made on this thread.
+    static ACTIVE: Cell<*const Budget> = const { Cell::new(ptr::null()) };
+    /// Whether an allocation on this thread failed due to the active budget.
+    static EXCEEDED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Space reserved in front of every block for the budget it was charged to
+/// and its size. Keeps the block aligned like a `malloc()` result.
+const HEADER_SIZE: usize = 16;
+
+#[repr(C)]
+struct Header {
+    budget: *const Budget,
+    size: usize,
+}
+
+const _: () = assert!(std::mem::size_of::<Header>() <= HEADER_SIZE);
+
+/// Construct an `ExifMem` that charges its allocations to the active budget.
+///
+/// Each block records the budget it was charged to, so it can be released
+/// properly regardless of the budget active when it is freed. Blocks
+/// allocated with no active budget are not charged.
+pub(crate) fn new_budget_mem() -> *mut ExifMem {
+    let mem = unsafe { exif_mem_new(Some(alloc_func), Some(realloc_func), Some(free_func)) };
+    assert!(!mem.is_null());
+    mem
+}
+
+unsafe extern "C" fn alloc_func(size: ExifLong) -> *mut c_void {
+    let budget = ACTIVE.with(|active| active.get());
+    let size = size as usize;
+    if !budget.is_null() && !(*budget).reserve(size) {
+        EXCEEDED.with(|exceeded| exceeded.set(true));
+        return ptr::null_mut();
+    }
+    // libexif expects zeroed memory, as its default allocator uses calloc()
+    let block = libc::calloc(1, HEADER_SIZE + size) as *mut Header;
+    if block.is_null() {
+        if !budget.is_null() {
+            (*budget).release(size);
+        }
+        return ptr::null_mut();
+    }
+    if !budget.is_null() {
+        // every block keeps the budget alive
+        Arc::increment_strong_count(budget);
+    }
+    block.write(Header { budget, size });
+    (block as *mut u8).add(HEADER_SIZE) as *mut c_void
+}
+
+unsafe extern "C" fn realloc_func(ptr: *mut c_void, size: ExifLong) -> *mut c_void {
+    if ptr.is_null() {
+        return alloc_func(size);
+    }
+    let block = (ptr as *mut u8).sub(HEADER_SIZE) as *mut Header;
+    let Header {
+        budget,
+        size: old_size,
+    } = block.read();
+    let size = size as usize;
+    if !budget.is_null() && size > old_size && !(*budget).reserve(size - old_size) {
+        EXCEEDED.with(|exceeded| exceeded.set(true));
+        return ptr::null_mut();
+    }
+    let new_block = libc::realloc(block as *mut c_void, HEADER_SIZE + size) as *mut Header;
+    if new_block.is_null() {
+        if !budget.is_null() && size > old_size {
+            (*budget).release(size - old_size);
+        }
+        return ptr::null_mut();
+    }
+    if !budget.is_null() && size < old_size {
+        (*budget).release(old_size - size);
+    }
+    (*new_block).size = size;
+    (new_block as *mut u8).add(HEADER_SIZE) as *mut c_void
+}
+
+unsafe extern "C" fn free_func(ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    let block = (ptr as *mut u8).sub(HEADER_SIZE) as *mut Header;
+    let Header { budget, size } = block.read();
+    if !budget.is_null() {
+        (*budget).release(size);
+        Arc::decrement_strong_count(budget);
+    }
+    libc::free(block as *mut c_void);
+}
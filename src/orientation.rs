@@ -0,0 +1,156 @@
+/// Orientation of the stored image relative to the scene, as recorded in the
+/// `Orientation` tag.
+///
+/// Each variant names the position of the stored image's first row and first
+/// column in the upright scene. For example, `RightTop` means that the first
+/// row of the stored image is the right side of the scene and the first
+/// column is its top, so the image must be rotated 90° clockwise to be
+/// displayed upright.
+///
+/// To display an image upright, mirror it horizontally if
+/// [`is_mirrored()`](#method.is_mirrored) returns `true`, and then rotate it
+/// clockwise by [`rotation()`](#method.rotation) degrees.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Orientation {
+    /// The image is upright.
+    TopLeft = 1,
+    /// The image is mirrored horizontally.
+    TopRight = 2,
+    /// The image is rotated by 180°.
+    BottomRight = 3,
+    /// The image is mirrored vertically.
+    BottomLeft = 4,
+    /// The image is mirrored along its top-left to bottom-right diagonal.
+    LeftTop = 5,
+    /// The image must be rotated 90° clockwise to be upright.
+    RightTop = 6,
+    /// The image is mirrored along its top-right to bottom-left diagonal.
+    RightBottom = 7,
+    /// The image must be rotated 90° counter-clockwise to be upright.
+    LeftBottom = 8,
+}
+
+impl TryFrom<u16> for Orientation {
+    type Error = super::Error;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Orientation::TopLeft),
+            2 => Ok(Orientation::TopRight),
+            3 => Ok(Orientation::BottomRight),
+            4 => Ok(Orientation::BottomLeft),
+            5 => Ok(Orientation::LeftTop),
+            6 => Ok(Orientation::RightTop),
+            7 => Ok(Orientation::RightBottom),
+            8 => Ok(Orientation::LeftBottom),
+            _ => Err(super::Error::IllegalOrientation),
+        }
+    }
+}
+
+impl From<Orientation> for u16 {
+    fn from(orientation: Orientation) -> u16 {
+        orientation as u16
+    }
+}
+
+impl Orientation {
+    /// Return the orientation whose correction mirrors the image horizontally
+    /// if `mirrored` is `true`, and then rotates it clockwise by `rotation`
+    /// degrees.
+    ///
+    /// Returns `None` if `rotation` is not a multiple of 90.
+    pub fn from_transform(mirrored: bool, rotation: u32) -> Option<Orientation> {
+        let quarters = rotation / 90;
+        if quarters * 90 != rotation {
+            return None;
+        }
+        Some(Orientation::from_parts(mirrored, quarters))
+    }
+
+    /// Return `true` if the image must be mirrored horizontally, before being
+    /// rotated, to be displayed upright.
+    pub fn is_mirrored(self) -> bool {
+        self.parts().0
+    }
+
+    /// Return the clockwise rotation in degrees (0, 90, 180 or 270) that
+    /// displays the image upright, after mirroring it if required.
+    pub fn rotation(self) -> u32 {
+        self.parts().1 * 90
+    }
+
+    /// Return `true` if the width and height of the upright image are swapped
+    /// relative to the stored image.
+    pub fn swaps_dimensions(self) -> bool {
+        self.parts().1 % 2 == 1
+    }
+
+    /// Return the orientation whose correction undoes the correction of this
+    /// orientation.
+    pub fn inverse(self) -> Orientation {
+        match self.parts() {
+            (false, quarters) => Orientation::from_parts(false, 4 - quarters),
+            // mirroring and then rotating is its own inverse
+            (true, _) => self,
+        }
+    }
+
+    /// Return the orientation whose correction is the correction of this
+    /// orientation followed by the correction of `next`.
+    ///
+    /// This describes how the orientation changes when the pixels of an image
+    /// are transformed. If the pixels of an image with orientation `current`
+    /// are transformed by the correction of `applied`, the new pixels have
+    /// the orientation `applied.inverse().then(current)`. For example, after
+    /// rotating the pixels of a `RightTop` image 90° clockwise, the image is
+    /// upright:
+    ///
+    /// ```
+    /// use libexif::Orientation;
+    ///
+    /// let applied = Orientation::RightTop;
+    /// let current = Orientation::RightTop;
+    /// assert_eq!(applied.inverse().then(current), Orientation::TopLeft);
+    /// ```
+    pub fn then(self, next: Orientation) -> Orientation {
+        let (mirrored, quarters) = self.parts();
+        let (next_mirrored, next_quarters) = next.parts();
+        // mirroring after a rotation equals mirroring before the opposite
+        // rotation
+        let quarters = if next_mirrored {
+            4 - quarters
+        } else {
+            quarters
+        };
+        Orientation::from_parts(mirrored ^ next_mirrored, quarters + next_quarters)
+    }
+
+    /// Decompose the correction into a horizontal mirroring and a number of
+    /// clockwise quarter turns.
+    fn parts(self) -> (bool, u32) {
+        match self {
+            Orientation::TopLeft => (false, 0),
+            Orientation::TopRight => (true, 0),
+            Orientation::BottomRight => (false, 2),
+            Orientation::BottomLeft => (true, 2),
+            Orientation::LeftTop => (true, 3),
+            Orientation::RightTop => (false, 1),
+            Orientation::RightBottom => (true, 1),
+            Orientation::LeftBottom => (false, 3),
+        }
+    }
+
+    fn from_parts(mirrored: bool, quarters: u32) -> Orientation {
+        match (mirrored, quarters % 4) {
+            (false, 0) => Orientation::TopLeft,
+            (true, 0) => Orientation::TopRight,
+            (false, 2) => Orientation::BottomRight,
+            (true, 2) => Orientation::BottomLeft,
+            (true, 3) => Orientation::LeftTop,
+            (false, 1) => Orientation::RightTop,
+            (true, 1) => Orientation::RightBottom,
+            _ => Orientation::LeftBottom,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! `Serialize` implementations for the types that wrap libexif objects.
+//!
+//! Tags are serialized as their numeric codes, which never change, while the
+//! names reported by libexif are included in entries for readability only.
+use crate::bits::IFD;
+use crate::content::Content;
+use crate::data::Data;
+use crate::entry::Entry;
+use crate::tag::Tag;
+use serde::ser::{Error, SerializeMap, SerializeSeq, SerializeStruct};
+use serde::{Serialize, Serializer};
+
+/// Serializes the data as a map from each non-empty IFD to its entries.
+impl Serialize for Data {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for content in self.contents().filter(|content| content.len() > 0) {
+            map.serialize_entry(&content.ifd().map_err(S::Error::custom)?, &content)?;
+        }
+        map.end()
+    }
+}
+
+/// Serializes the content as a sequence of its entries.
+impl<'a> Serialize for Content<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for entry in self.entries() {
+            seq.serialize_element(&entry)?;
+        }
+        seq.end()
+    }
+}
+
+/// Serializes the entry as a structure holding its tag, the name of the tag
+/// (or `null` if libexif does not know it), its data type and its value.
+impl<'a> Serialize for Entry<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (value, _) = self
+            .value_lenient(self.byte_order().map_err(S::Error::custom)?)
+            .map_err(S::Error::custom)?;
+        let tag = self.tag();
+        let name = IFD::try_from(self.ifd())
+            .ok()
+            .and_then(|ifd| tag.name(ifd).ok());
+        let data_type = self.data_type().map_err(S::Error::custom)?;
+        let mut state = serializer.serialize_struct("Entry", 4)?;
+        state.serialize_field("tag", &tag)?;
+        state.serialize_field("name", &name)?;
+        state.serialize_field("data_type", &data_type)?;
+        state.serialize_field("value", &value)?;
+        state.end()
+    }
+}
+
+/// Serializes the tag as its numeric code.
+impl Serialize for Tag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.code())
+    }
+}
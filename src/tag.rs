@@ -2,10 +2,35 @@
 // Copyright (c) 2016 David Cuddeback
 //
 use crate::bits::*;
+use libc::{c_char, c_uint};
 use libexif_sys::*;
-use std::{ffi::CStr, str::Utf8Error};
+use std::ffi::{CStr, CString};
+
+/// All IFDs, in the order of libexif's `ExifIfd` values.
+const IFDS: [IFD; 5] = [
+    IFD::Image,
+    IFD::Thumbnail,
+    IFD::EXIF,
+    IFD::GPS,
+    IFD::Interoperability,
+];
+
+/// All data encodings, in the order of libexif's `ExifDataType` values.
+const ENCODINGS: [DataEncoding; 5] = [
+    DataEncoding::Chunky,
+    DataEncoding::Planar,
+    DataEncoding::Ycc,
+    DataEncoding::Compressed,
+    DataEncoding::Unknown,
+];
 
 /// EXIF tag.
+///
+/// Constants are provided for every tag in libexif's tag table, named after
+/// libexif's `EXIF_TAG_*` values. Note that tags are identified by their code
+/// alone, and some codes are used by more than one IFD: for example
+/// `Tag::GPS_LATITUDE_REF` and `Tag::INTEROPERABILITY_INDEX` are equal.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Tag {
     inner: ExifTag,
 }
@@ -17,33 +42,407 @@ impl From<ExifTag> for Tag {
 }
 
 impl Tag {
+    // Interoperability IFD
+    /// `InteroperabilityIndex` (0x0001).
+    pub const INTEROPERABILITY_INDEX: Tag = Tag { inner: 0x0001 };
+    /// `InteroperabilityVersion` (0x0002).
+    pub const INTEROPERABILITY_VERSION: Tag = Tag { inner: 0x0002 };
+
+    // IFD0, IFD1 and EXIF IFD
+    /// `NewSubfileType` (0x00fe).
+    pub const NEW_SUBFILE_TYPE: Tag = Tag { inner: 0x00fe };
+    /// `ImageWidth` (0x0100).
+    pub const IMAGE_WIDTH: Tag = Tag { inner: 0x0100 };
+    /// `ImageLength` (0x0101).
+    pub const IMAGE_LENGTH: Tag = Tag { inner: 0x0101 };
+    /// `BitsPerSample` (0x0102).
+    pub const BITS_PER_SAMPLE: Tag = Tag { inner: 0x0102 };
+    /// `Compression` (0x0103).
+    pub const COMPRESSION: Tag = Tag { inner: 0x0103 };
+    /// `PhotometricInterpretation` (0x0106).
+    pub const PHOTOMETRIC_INTERPRETATION: Tag = Tag { inner: 0x0106 };
+    /// `FillOrder` (0x010a).
+    pub const FILL_ORDER: Tag = Tag { inner: 0x010a };
+    /// `DocumentName` (0x010d).
+    pub const DOCUMENT_NAME: Tag = Tag { inner: 0x010d };
+    /// `ImageDescription` (0x010e).
+    pub const IMAGE_DESCRIPTION: Tag = Tag { inner: 0x010e };
+    /// `Make` (0x010f).
+    pub const MAKE: Tag = Tag { inner: 0x010f };
+    /// `Model` (0x0110).
+    pub const MODEL: Tag = Tag { inner: 0x0110 };
+    /// `StripOffsets` (0x0111).
+    pub const STRIP_OFFSETS: Tag = Tag { inner: 0x0111 };
+    /// `Orientation` (0x0112).
+    pub const ORIENTATION: Tag = Tag { inner: 0x0112 };
+    /// `SamplesPerPixel` (0x0115).
+    pub const SAMPLES_PER_PIXEL: Tag = Tag { inner: 0x0115 };
+    /// `RowsPerStrip` (0x0116).
+    pub const ROWS_PER_STRIP: Tag = Tag { inner: 0x0116 };
+    /// `StripByteCounts` (0x0117).
+    pub const STRIP_BYTE_COUNTS: Tag = Tag { inner: 0x0117 };
+    /// `XResolution` (0x011a).
+    pub const X_RESOLUTION: Tag = Tag { inner: 0x011a };
+    /// `YResolution` (0x011b).
+    pub const Y_RESOLUTION: Tag = Tag { inner: 0x011b };
+    /// `PlanarConfiguration` (0x011c).
+    pub const PLANAR_CONFIGURATION: Tag = Tag { inner: 0x011c };
+    /// `ResolutionUnit` (0x0128).
+    pub const RESOLUTION_UNIT: Tag = Tag { inner: 0x0128 };
+    /// `TransferFunction` (0x012d).
+    pub const TRANSFER_FUNCTION: Tag = Tag { inner: 0x012d };
+    /// `Software` (0x0131).
+    pub const SOFTWARE: Tag = Tag { inner: 0x0131 };
+    /// `DateTime` (0x0132).
+    pub const DATE_TIME: Tag = Tag { inner: 0x0132 };
+    /// `Artist` (0x013b).
+    pub const ARTIST: Tag = Tag { inner: 0x013b };
+    /// `WhitePoint` (0x013e).
+    pub const WHITE_POINT: Tag = Tag { inner: 0x013e };
+    /// `PrimaryChromaticities` (0x013f).
+    pub const PRIMARY_CHROMATICITIES: Tag = Tag { inner: 0x013f };
+    /// `SubIFDs` (0x014a).
+    pub const SUB_IFDS: Tag = Tag { inner: 0x014a };
+    /// `TransferRange` (0x0156).
+    pub const TRANSFER_RANGE: Tag = Tag { inner: 0x0156 };
+    /// `JPEGProc` (0x0200).
+    pub const JPEG_PROC: Tag = Tag { inner: 0x0200 };
+    /// `JPEGInterchangeFormat` (0x0201).
+    pub const JPEG_INTERCHANGE_FORMAT: Tag = Tag { inner: 0x0201 };
+    /// `JPEGInterchangeFormatLength` (0x0202).
+    pub const JPEG_INTERCHANGE_FORMAT_LENGTH: Tag = Tag { inner: 0x0202 };
+    /// `YCbCrCoefficients` (0x0211).
+    pub const YCBCR_COEFFICIENTS: Tag = Tag { inner: 0x0211 };
+    /// `YCbCrSubSampling` (0x0212).
+    pub const YCBCR_SUB_SAMPLING: Tag = Tag { inner: 0x0212 };
+    /// `YCbCrPositioning` (0x0213).
+    pub const YCBCR_POSITIONING: Tag = Tag { inner: 0x0213 };
+    /// `ReferenceBlackWhite` (0x0214).
+    pub const REFERENCE_BLACK_WHITE: Tag = Tag { inner: 0x0214 };
+    /// `XMLPacket` (0x02bc).
+    pub const XML_PACKET: Tag = Tag { inner: 0x02bc };
+    /// `RelatedImageFileFormat` (0x1000).
+    pub const RELATED_IMAGE_FILE_FORMAT: Tag = Tag { inner: 0x1000 };
+    /// `RelatedImageWidth` (0x1001).
+    pub const RELATED_IMAGE_WIDTH: Tag = Tag { inner: 0x1001 };
+    /// `RelatedImageLength` (0x1002).
+    pub const RELATED_IMAGE_LENGTH: Tag = Tag { inner: 0x1002 };
+    /// `ImageDepth` (0x80e5).
+    pub const IMAGE_DEPTH: Tag = Tag { inner: 0x80e5 };
+    /// `CFARepeatPatternDim` (0x828d).
+    pub const CFA_REPEAT_PATTERN_DIM: Tag = Tag { inner: 0x828d };
+    /// `CFAPattern` (0x828e).
+    pub const CFA_PATTERN: Tag = Tag { inner: 0x828e };
+    /// `BatteryLevel` (0x828f).
+    pub const BATTERY_LEVEL: Tag = Tag { inner: 0x828f };
+    /// `Copyright` (0x8298).
+    pub const COPYRIGHT: Tag = Tag { inner: 0x8298 };
+    /// `ExposureTime` (0x829a).
+    pub const EXPOSURE_TIME: Tag = Tag { inner: 0x829a };
+    /// `FNumber` (0x829d).
+    pub const FNUMBER: Tag = Tag { inner: 0x829d };
+    /// `IPTC/NAA` (0x83bb).
+    pub const IPTC_NAA: Tag = Tag { inner: 0x83bb };
+    /// `ImageResources` (0x8649).
+    pub const IMAGE_RESOURCES: Tag = Tag { inner: 0x8649 };
+    /// `ExifIfdPointer` (0x8769).
+    pub const EXIF_IFD_POINTER: Tag = Tag { inner: 0x8769 };
+    /// `InterColorProfile` (0x8773).
+    pub const INTER_COLOR_PROFILE: Tag = Tag { inner: 0x8773 };
+    /// `ExposureProgram` (0x8822).
+    pub const EXPOSURE_PROGRAM: Tag = Tag { inner: 0x8822 };
+    /// `SpectralSensitivity` (0x8824).
+    pub const SPECTRAL_SENSITIVITY: Tag = Tag { inner: 0x8824 };
+    /// `GPSInfoIFDPointer` (0x8825).
+    pub const GPS_INFO_IFD_POINTER: Tag = Tag { inner: 0x8825 };
+    /// `ISOSpeedRatings` (0x8827).
+    pub const ISO_SPEED_RATINGS: Tag = Tag { inner: 0x8827 };
+    /// `OECF` (0x8828).
+    pub const OECF: Tag = Tag { inner: 0x8828 };
+    /// `TimeZoneOffset` (0x882a).
+    pub const TIME_ZONE_OFFSET: Tag = Tag { inner: 0x882a };
+    /// `SensitivityType` (0x8830).
+    pub const SENSITIVITY_TYPE: Tag = Tag { inner: 0x8830 };
+    /// `StandardOutputSensitivity` (0x8831).
+    pub const STANDARD_OUTPUT_SENSITIVITY: Tag = Tag { inner: 0x8831 };
+    /// `RecommendedExposureIndex` (0x8832).
+    pub const RECOMMENDED_EXPOSURE_INDEX: Tag = Tag { inner: 0x8832 };
+    /// `ISOSpeed` (0x8833).
+    pub const ISO_SPEED: Tag = Tag { inner: 0x8833 };
+    /// `ISOSpeedLatitudeyyy` (0x8834).
+    pub const ISO_SPEED_LATITUDE_YYY: Tag = Tag { inner: 0x8834 };
+    /// `ISOSpeedLatitudezzz` (0x8835).
+    pub const ISO_SPEED_LATITUDE_ZZZ: Tag = Tag { inner: 0x8835 };
+    /// `ExifVersion` (0x9000).
+    pub const EXIF_VERSION: Tag = Tag { inner: 0x9000 };
+    /// `DateTimeOriginal` (0x9003).
+    pub const DATE_TIME_ORIGINAL: Tag = Tag { inner: 0x9003 };
+    /// `DateTimeDigitized` (0x9004).
+    pub const DATE_TIME_DIGITIZED: Tag = Tag { inner: 0x9004 };
+    /// `OffsetTime` (0x9010).
+    pub const OFFSET_TIME: Tag = Tag { inner: 0x9010 };
+    /// `OffsetTimeOriginal` (0x9011).
+    pub const OFFSET_TIME_ORIGINAL: Tag = Tag { inner: 0x9011 };
+    /// `OffsetTimeDigitized` (0x9012).
+    pub const OFFSET_TIME_DIGITIZED: Tag = Tag { inner: 0x9012 };
+    /// `ComponentsConfiguration` (0x9101).
+    pub const COMPONENTS_CONFIGURATION: Tag = Tag { inner: 0x9101 };
+    /// `CompressedBitsPerPixel` (0x9102).
+    pub const COMPRESSED_BITS_PER_PIXEL: Tag = Tag { inner: 0x9102 };
+    /// `ShutterSpeedValue` (0x9201).
+    pub const SHUTTER_SPEED_VALUE: Tag = Tag { inner: 0x9201 };
+    /// `ApertureValue` (0x9202).
+    pub const APERTURE_VALUE: Tag = Tag { inner: 0x9202 };
+    /// `BrightnessValue` (0x9203).
+    pub const BRIGHTNESS_VALUE: Tag = Tag { inner: 0x9203 };
+    /// `ExposureBiasValue` (0x9204).
+    pub const EXPOSURE_BIAS_VALUE: Tag = Tag { inner: 0x9204 };
+    /// `MaxApertureValue` (0x9205).
+    pub const MAX_APERTURE_VALUE: Tag = Tag { inner: 0x9205 };
+    /// `SubjectDistance` (0x9206).
+    pub const SUBJECT_DISTANCE: Tag = Tag { inner: 0x9206 };
+    /// `MeteringMode` (0x9207).
+    pub const METERING_MODE: Tag = Tag { inner: 0x9207 };
+    /// `LightSource` (0x9208).
+    pub const LIGHT_SOURCE: Tag = Tag { inner: 0x9208 };
+    /// `Flash` (0x9209).
+    pub const FLASH: Tag = Tag { inner: 0x9209 };
+    /// `FocalLength` (0x920a).
+    pub const FOCAL_LENGTH: Tag = Tag { inner: 0x920a };
+    /// `SubjectArea` (0x9214).
+    pub const SUBJECT_AREA: Tag = Tag { inner: 0x9214 };
+    /// `TIFF/EPStandardID` (0x9216).
+    pub const TIFF_EP_STANDARD_ID: Tag = Tag { inner: 0x9216 };
+    /// `MakerNote` (0x927c).
+    pub const MAKER_NOTE: Tag = Tag { inner: 0x927c };
+    /// `UserComment` (0x9286).
+    pub const USER_COMMENT: Tag = Tag { inner: 0x9286 };
+    /// `SubsecTime` (0x9290).
+    pub const SUB_SEC_TIME: Tag = Tag { inner: 0x9290 };
+    /// `SubSecTimeOriginal` (0x9291).
+    pub const SUB_SEC_TIME_ORIGINAL: Tag = Tag { inner: 0x9291 };
+    /// `SubSecTimeDigitized` (0x9292).
+    pub const SUB_SEC_TIME_DIGITIZED: Tag = Tag { inner: 0x9292 };
+    /// `XPTitle` (0x9c9b).
+    pub const XP_TITLE: Tag = Tag { inner: 0x9c9b };
+    /// `XPComment` (0x9c9c).
+    pub const XP_COMMENT: Tag = Tag { inner: 0x9c9c };
+    /// `XPAuthor` (0x9c9d).
+    pub const XP_AUTHOR: Tag = Tag { inner: 0x9c9d };
+    /// `XPKeywords` (0x9c9e).
+    pub const XP_KEYWORDS: Tag = Tag { inner: 0x9c9e };
+    /// `XPSubject` (0x9c9f).
+    pub const XP_SUBJECT: Tag = Tag { inner: 0x9c9f };
+    /// `FlashPixVersion` (0xa000).
+    pub const FLASH_PIX_VERSION: Tag = Tag { inner: 0xa000 };
+    /// `ColorSpace` (0xa001).
+    pub const COLOR_SPACE: Tag = Tag { inner: 0xa001 };
+    /// `PixelXDimension` (0xa002).
+    pub const PIXEL_X_DIMENSION: Tag = Tag { inner: 0xa002 };
+    /// `PixelYDimension` (0xa003).
+    pub const PIXEL_Y_DIMENSION: Tag = Tag { inner: 0xa003 };
+    /// `RelatedSoundFile` (0xa004).
+    pub const RELATED_SOUND_FILE: Tag = Tag { inner: 0xa004 };
+    /// `InteroperabilityIFDPointer` (0xa005).
+    pub const INTEROPERABILITY_IFD_POINTER: Tag = Tag { inner: 0xa005 };
+    /// `FlashEnergy` (0xa20b).
+    pub const FLASH_ENERGY: Tag = Tag { inner: 0xa20b };
+    /// `SpatialFrequencyResponse` (0xa20c).
+    pub const SPATIAL_FREQUENCY_RESPONSE: Tag = Tag { inner: 0xa20c };
+    /// `FocalPlaneXResolution` (0xa20e).
+    pub const FOCAL_PLANE_X_RESOLUTION: Tag = Tag { inner: 0xa20e };
+    /// `FocalPlaneYResolution` (0xa20f).
+    pub const FOCAL_PLANE_Y_RESOLUTION: Tag = Tag { inner: 0xa20f };
+    /// `FocalPlaneResolutionUnit` (0xa210).
+    pub const FOCAL_PLANE_RESOLUTION_UNIT: Tag = Tag { inner: 0xa210 };
+    /// `SubjectLocation` (0xa214).
+    pub const SUBJECT_LOCATION: Tag = Tag { inner: 0xa214 };
+    /// `ExposureIndex` (0xa215).
+    pub const EXPOSURE_INDEX: Tag = Tag { inner: 0xa215 };
+    /// `SensingMethod` (0xa217).
+    pub const SENSING_METHOD: Tag = Tag { inner: 0xa217 };
+    /// `FileSource` (0xa300).
+    pub const FILE_SOURCE: Tag = Tag { inner: 0xa300 };
+    /// `SceneType` (0xa301).
+    pub const SCENE_TYPE: Tag = Tag { inner: 0xa301 };
+    /// `CFAPattern` (0xa302).
+    pub const NEW_CFA_PATTERN: Tag = Tag { inner: 0xa302 };
+    /// `CustomRendered` (0xa401).
+    pub const CUSTOM_RENDERED: Tag = Tag { inner: 0xa401 };
+    /// `ExposureMode` (0xa402).
+    pub const EXPOSURE_MODE: Tag = Tag { inner: 0xa402 };
+    /// `WhiteBalance` (0xa403).
+    pub const WHITE_BALANCE: Tag = Tag { inner: 0xa403 };
+    /// `DigitalZoomRatio` (0xa404).
+    pub const DIGITAL_ZOOM_RATIO: Tag = Tag { inner: 0xa404 };
+    /// `FocalLengthIn35mmFilm` (0xa405).
+    pub const FOCAL_LENGTH_IN_35MM_FILM: Tag = Tag { inner: 0xa405 };
+    /// `SceneCaptureType` (0xa406).
+    pub const SCENE_CAPTURE_TYPE: Tag = Tag { inner: 0xa406 };
+    /// `GainControl` (0xa407).
+    pub const GAIN_CONTROL: Tag = Tag { inner: 0xa407 };
+    /// `Contrast` (0xa408).
+    pub const CONTRAST: Tag = Tag { inner: 0xa408 };
+    /// `Saturation` (0xa409).
+    pub const SATURATION: Tag = Tag { inner: 0xa409 };
+    /// `Sharpness` (0xa40a).
+    pub const SHARPNESS: Tag = Tag { inner: 0xa40a };
+    /// `DeviceSettingDescription` (0xa40b).
+    pub const DEVICE_SETTING_DESCRIPTION: Tag = Tag { inner: 0xa40b };
+    /// `SubjectDistanceRange` (0xa40c).
+    pub const SUBJECT_DISTANCE_RANGE: Tag = Tag { inner: 0xa40c };
+    /// `ImageUniqueID` (0xa420).
+    pub const IMAGE_UNIQUE_ID: Tag = Tag { inner: 0xa420 };
+    /// `CameraOwnerName` (0xa430).
+    pub const CAMERA_OWNER_NAME: Tag = Tag { inner: 0xa430 };
+    /// `BodySerialNumber` (0xa431).
+    pub const BODY_SERIAL_NUMBER: Tag = Tag { inner: 0xa431 };
+    /// `LensSpecification` (0xa432).
+    pub const LENS_SPECIFICATION: Tag = Tag { inner: 0xa432 };
+    /// `LensMake` (0xa433).
+    pub const LENS_MAKE: Tag = Tag { inner: 0xa433 };
+    /// `LensModel` (0xa434).
+    pub const LENS_MODEL: Tag = Tag { inner: 0xa434 };
+    /// `LensSerialNumber` (0xa435).
+    pub const LENS_SERIAL_NUMBER: Tag = Tag { inner: 0xa435 };
+    /// `CompositeImage` (0xa460).
+    pub const COMPOSITE_IMAGE: Tag = Tag { inner: 0xa460 };
+    /// `SourceImageNumberOfCompositeImage` (0xa461).
+    pub const SOURCE_IMAGE_NUMBER_OF_COMPOSITE_IMAGE: Tag = Tag { inner: 0xa461 };
+    /// `SourceExposureTimesOfCompositeImage` (0xa462).
+    pub const SOURCE_EXPOSURE_TIMES_OF_COMPOSITE_IMAGE: Tag = Tag { inner: 0xa462 };
+    /// `Gamma` (0xa500).
+    pub const GAMMA: Tag = Tag { inner: 0xa500 };
+    /// `PrintImageMatching` (0xc4a5).
+    pub const PRINT_IMAGE_MATCHING: Tag = Tag { inner: 0xc4a5 };
+    /// `Padding` (0xea1c).
+    pub const PADDING: Tag = Tag { inner: 0xea1c };
+
+    // GPS IFD
+    /// `GPSVersionID` (0x0000).
+    pub const GPS_VERSION_ID: Tag = Tag { inner: 0x0000 };
+    /// `GPSLatitudeRef` (0x0001).
+    pub const GPS_LATITUDE_REF: Tag = Tag { inner: 0x0001 };
+    /// `GPSLatitude` (0x0002).
+    pub const GPS_LATITUDE: Tag = Tag { inner: 0x0002 };
+    /// `GPSLongitudeRef` (0x0003).
+    pub const GPS_LONGITUDE_REF: Tag = Tag { inner: 0x0003 };
+    /// `GPSLongitude` (0x0004).
+    pub const GPS_LONGITUDE: Tag = Tag { inner: 0x0004 };
+    /// `GPSAltitudeRef` (0x0005).
+    pub const GPS_ALTITUDE_REF: Tag = Tag { inner: 0x0005 };
+    /// `GPSAltitude` (0x0006).
+    pub const GPS_ALTITUDE: Tag = Tag { inner: 0x0006 };
+    /// `GPSTimeStamp` (0x0007).
+    pub const GPS_TIME_STAMP: Tag = Tag { inner: 0x0007 };
+    /// `GPSSatellites` (0x0008).
+    pub const GPS_SATELLITES: Tag = Tag { inner: 0x0008 };
+    /// `GPSStatus` (0x0009).
+    pub const GPS_STATUS: Tag = Tag { inner: 0x0009 };
+    /// `GPSMeasureMode` (0x000a).
+    pub const GPS_MEASURE_MODE: Tag = Tag { inner: 0x000a };
+    /// `GPSDOP` (0x000b).
+    pub const GPS_DOP: Tag = Tag { inner: 0x000b };
+    /// `GPSSpeedRef` (0x000c).
+    pub const GPS_SPEED_REF: Tag = Tag { inner: 0x000c };
+    /// `GPSSpeed` (0x000d).
+    pub const GPS_SPEED: Tag = Tag { inner: 0x000d };
+    /// `GPSTrackRef` (0x000e).
+    pub const GPS_TRACK_REF: Tag = Tag { inner: 0x000e };
+    /// `GPSTrack` (0x000f).
+    pub const GPS_TRACK: Tag = Tag { inner: 0x000f };
+    /// `GPSImgDirectionRef` (0x0010).
+    pub const GPS_IMG_DIRECTION_REF: Tag = Tag { inner: 0x0010 };
+    /// `GPSImgDirection` (0x0011).
+    pub const GPS_IMG_DIRECTION: Tag = Tag { inner: 0x0011 };
+    /// `GPSMapDatum` (0x0012).
+    pub const GPS_MAP_DATUM: Tag = Tag { inner: 0x0012 };
+    /// `GPSDestLatitudeRef` (0x0013).
+    pub const GPS_DEST_LATITUDE_REF: Tag = Tag { inner: 0x0013 };
+    /// `GPSDestLatitude` (0x0014).
+    pub const GPS_DEST_LATITUDE: Tag = Tag { inner: 0x0014 };
+    /// `GPSDestLongitudeRef` (0x0015).
+    pub const GPS_DEST_LONGITUDE_REF: Tag = Tag { inner: 0x0015 };
+    /// `GPSDestLongitude` (0x0016).
+    pub const GPS_DEST_LONGITUDE: Tag = Tag { inner: 0x0016 };
+    /// `GPSDestBearingRef` (0x0017).
+    pub const GPS_DEST_BEARING_REF: Tag = Tag { inner: 0x0017 };
+    /// `GPSDestBearing` (0x0018).
+    pub const GPS_DEST_BEARING: Tag = Tag { inner: 0x0018 };
+    /// `GPSDestDistanceRef` (0x0019).
+    pub const GPS_DEST_DISTANCE_REF: Tag = Tag { inner: 0x0019 };
+    /// `GPSDestDistance` (0x001a).
+    pub const GPS_DEST_DISTANCE: Tag = Tag { inner: 0x001a };
+    /// `GPSProcessingMethod` (0x001b).
+    pub const GPS_PROCESSING_METHOD: Tag = Tag { inner: 0x001b };
+    /// `GPSAreaInformation` (0x001c).
+    pub const GPS_AREA_INFORMATION: Tag = Tag { inner: 0x001c };
+    /// `GPSDateStamp` (0x001d).
+    pub const GPS_DATE_STAMP: Tag = Tag { inner: 0x001d };
+    /// `GPSDifferential` (0x001e).
+    pub const GPS_DIFFERENTIAL: Tag = Tag { inner: 0x001e };
+    /// `GPSHPositioningError` (0x001f).
+    pub const GPS_H_POSITIONING_ERROR: Tag = Tag { inner: 0x001f };
+
+    /// Iterate over all of the tags known to libexif.
+    ///
+    /// The tags are yielded in the order of libexif's tag table. A tag code
+    /// that has different meanings in different IFDs appears once for each
+    /// meaning.
+    pub fn table() -> impl ExactSizeIterator<Item = TagInfo> {
+        let mut count = unsafe { exif_tag_table_count() };
+        // the table is terminated by an entry without a name
+        while count > 0 && unsafe { exif_tag_table_get_name(count - 1) }.is_null() {
+            count -= 1;
+        }
+        TagTable { index: 0, count }
+    }
+
+    /// Look up a tag by its name, such as `"Orientation"`.
+    pub fn from_name(name: &str) -> Option<Tag> {
+        let cname = CString::new(name).ok()?;
+        let tag = unsafe { exif_tag_from_name(cname.as_ptr()) };
+        // libexif returns 0 for unknown names, which is also GPSVersionID
+        if tag == 0 && name != "GPSVersionID" {
+            None
+        } else {
+            Some(Tag::from(tag))
+        }
+    }
+
     /// Return the tag code (for example, 274 for Orientation).
     pub fn code(&self) -> u32 {
         self.inner
     }
 
     /// The name of the EXIF tag when found in the given IFD.
-    pub fn name(&self, ifd: IFD) -> Result<&str, Utf8Error> {
+    pub fn name(&self, ifd: IFD) -> Result<&str, super::Error> {
         let ptr = unsafe { exif_tag_get_name_in_ifd(self.inner, ifd.into()) };
-        assert!(!ptr.is_null());
+        if ptr.is_null() {
+            return Err(super::Error::UnknownTag);
+        }
         let cstr = unsafe { CStr::from_ptr(ptr) };
-        cstr.to_str()
+        Ok(cstr.to_str()?)
     }
 
     /// The title of the EXIF tag when found in the given IFD.
-    pub fn title(&self, ifd: IFD) -> Result<&str, Utf8Error> {
+    pub fn title(&self, ifd: IFD) -> Result<&str, super::Error> {
         let ptr = unsafe { exif_tag_get_title_in_ifd(self.inner, ifd.into()) };
-        assert!(!ptr.is_null());
+        if ptr.is_null() {
+            return Err(super::Error::UnknownTag);
+        }
         let cstr = unsafe { CStr::from_ptr(ptr) };
-        cstr.to_str()
+        Ok(cstr.to_str()?)
     }
 
     /// A verbose description of the EXIF tag when found in the given IFD.
-    pub fn description(&self, ifd: IFD) -> Result<&str, Utf8Error> {
+    pub fn description(&self, ifd: IFD) -> Result<&str, super::Error> {
         let ptr = unsafe { exif_tag_get_description_in_ifd(self.inner, ifd.into()) };
-        assert!(!ptr.is_null());
+        if ptr.is_null() {
+            return Err(super::Error::UnknownTag);
+        }
         let cstr = unsafe { CStr::from_ptr(ptr) };
-        cstr.to_str()
+        Ok(cstr.to_str()?)
     }
 
     /// The EXIF tag's support level with the given IFD and encoding.
@@ -59,3 +458,119 @@ impl Tag {
         SupportLevel::try_from(support_level)
     }
 }
+
+/// Description of a tag from libexif's tag table.
+#[derive(Debug, Clone)]
+pub struct TagInfo {
+    tag: Tag,
+    name: &'static str,
+    title: &'static str,
+    description: &'static str,
+    support_levels: [[SupportLevel; 5]; 5],
+}
+
+impl TagInfo {
+    /// The tag being described.
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// The name of the tag, such as `"Orientation"`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The title of the tag, suitable for display.
+    pub fn title(&self) -> &'static str {
+        self.title
+    }
+
+    /// A verbose description of the tag.
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// The tag's support level in the given IFD with the given encoding.
+    ///
+    /// IFDs in which the tag's code has a different meaning report
+    /// `SupportLevel::NotAllowed`.
+    pub fn support_level(&self, ifd: IFD, encoding: DataEncoding) -> SupportLevel {
+        let ifd: ExifIfd = ifd.into();
+        let encoding: ExifDataType = encoding.into();
+        self.support_levels[ifd as usize][encoding as usize]
+    }
+}
+
+struct TagTable {
+    index: c_uint,
+    count: c_uint,
+}
+
+impl Iterator for TagTable {
+    type Item = TagInfo;
+
+    fn next(&mut self) -> Option<TagInfo> {
+        if self.index < self.count {
+            let name = unsafe { static_str(exif_tag_table_get_name(self.index)) };
+            let tag = Tag::from(unsafe { exif_tag_table_get_tag(self.index) });
+            self.index += 1;
+            Some(TagInfo::new(tag, name.unwrap_or_default()))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.count - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for TagTable {
+    fn len(&self) -> usize {
+        (self.count - self.index) as usize
+    }
+}
+
+impl TagInfo {
+    fn new(tag: Tag, name: &'static str) -> TagInfo {
+        let mut support_levels = [[SupportLevel::NotAllowed; 5]; 5];
+        let mut title = None;
+        let mut description = None;
+        for ifd in IFDS {
+            // the same code may belong to another tag in this IFD
+            let ifd_name = unsafe { static_str(exif_tag_get_name_in_ifd(tag.code(), ifd.into())) };
+            if ifd_name != Some(name) {
+                continue;
+            }
+            let index: ExifIfd = ifd.into();
+            for encoding in ENCODINGS {
+                let level = tag.support_level(ifd, encoding);
+                let encoding: ExifDataType = encoding.into();
+                support_levels[index as usize][encoding as usize] =
+                    level.unwrap_or(SupportLevel::Unknown);
+            }
+            if title.is_none() {
+                title = unsafe { static_str(exif_tag_get_title_in_ifd(tag.code(), ifd.into())) };
+                description =
+                    unsafe { static_str(exif_tag_get_description_in_ifd(tag.code(), ifd.into())) };
+            }
+        }
+        TagInfo {
+            tag,
+            name,
+            title: title.unwrap_or(name),
+            description: description.unwrap_or_default(),
+            support_levels,
+        }
+    }
+}
+
+/// Convert a string from libexif's static tables.
+unsafe fn static_str(ptr: *const c_char) -> Option<&'static str> {
+    if ptr.is_null() {
+        None
+    } else {
+        CStr::from_ptr(ptr).to_str().ok()
+    }
+}
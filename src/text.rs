@@ -0,0 +1,120 @@
+//! Parsing and formatting of values written as text, shared by the date and
+//! time accessors and the conversions to other formats.
+#[cfg(any(feature = "json", feature = "xmp"))]
+use crate::value::Value;
+
+/// Character code of a comment in ASCII, such as a `UserComment`.
+#[cfg(any(feature = "json", feature = "xmp"))]
+pub(crate) const ASCII_COMMENT: &[u8; 8] = b"ASCII\0\0\0";
+
+/// Largest denominator used when converting decimal numbers to rationals.
+#[cfg(any(feature = "json", feature = "xmp"))]
+const MAX_DENOMINATOR: u64 = 1_000_000;
+
+/// Parse a rational written as `n/d` or as a decimal number.
+#[cfg(any(feature = "json", feature = "xmp"))]
+pub(crate) fn parse_rational(token: &str) -> Option<(i64, i64)> {
+    if let Some((numerator, denominator)) = token.split_once('/') {
+        return Some((numerator.parse().ok()?, denominator.parse().ok()?));
+    }
+    if let Ok(integer) = token.parse() {
+        return Some((integer, 1));
+    }
+    let value: f64 = token.parse().ok()?;
+    let (numerator, denominator) = approximate(value.abs())?;
+    let numerator = numerator as i64;
+    Some((
+        if value < 0.0 { -numerator } else { numerator },
+        denominator as i64,
+    ))
+}
+
+/// Find the fraction closest to a non-negative number using continued
+/// fractions, so that values such as 0.004 become 1/250.
+#[cfg(any(feature = "json", feature = "xmp"))]
+fn approximate(value: f64) -> Option<(u64, u64)> {
+    if !value.is_finite() || value > u32::MAX as f64 {
+        return None;
+    }
+    // the two previous convergents
+    let (mut h0, mut h1) = (0u64, 1u64);
+    let (mut k0, mut k1) = (1u64, 0u64);
+    let mut x = value;
+    for _ in 0..64 {
+        let a = x.floor();
+        let Some(h2) = (a as u64).checked_mul(h1).and_then(|h| h.checked_add(h0)) else {
+            break;
+        };
+        let Some(k2) = (a as u64).checked_mul(k1).and_then(|k| k.checked_add(k0)) else {
+            break;
+        };
+        if h2 > u32::MAX as u64 || k2 > MAX_DENOMINATOR {
+            break;
+        }
+        (h0, h1, k0, k1) = (h1, h2, k1, k2);
+        let fraction = x - a;
+        if fraction < 1e-9 || (h1 as f64 / k1 as f64 - value).abs() <= value * 1e-12 {
+            break;
+        }
+        x = 1.0 / fraction;
+    }
+    if k1 == 0 {
+        None
+    } else {
+        Some((h1, k1))
+    }
+}
+
+/// Return the text of a comment in ASCII or with an undefined character
+/// code, without trailing padding.
+#[cfg(any(feature = "json", feature = "xmp"))]
+pub(crate) fn read_comment(value: &Value) -> Option<String> {
+    let Value::Undefined(bytes) = value else {
+        return None;
+    };
+    if bytes.len() < ASCII_COMMENT.len() {
+        return None;
+    }
+    let (code, comment) = bytes.split_at(ASCII_COMMENT.len());
+    if code != ASCII_COMMENT && code.iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    let comment = std::str::from_utf8(comment).ok()?;
+    Some(comment.trim_end_matches([' ', '\0']).to_string())
+}
+
+/// Parse an offset from UTC written as `+hh:mm`, `-hh:mm` or `Z`, in
+/// minutes.
+#[cfg(any(feature = "chrono", feature = "xmp"))]
+pub(crate) fn parse_offset(text: &str) -> Option<i32> {
+    let text = text.trim();
+    if text == "Z" {
+        return Some(0);
+    }
+    let sign = match text.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = text[1..].split_once(':')?;
+    let digits = |field: &str| {
+        if field.len() == 2 && field.bytes().all(|b| b.is_ascii_digit()) {
+            field.parse::<i32>().ok()
+        } else {
+            None
+        }
+    };
+    let (hours, minutes) = (digits(hours)?, digits(minutes)?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// Format an offset from UTC in minutes as `+hh:mm` or `-hh:mm`.
+#[cfg(any(feature = "chrono", feature = "xmp"))]
+pub(crate) fn format_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.unsigned_abs();
+    format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
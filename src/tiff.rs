@@ -0,0 +1,147 @@
+//! Minimal reader for TIFF-style image file directories.
+//!
+//! libexif only exposes maker note values as formatted text, so the raw values
+//! are decoded here from the value of the MakerNote entry.
+use crate::bits::*;
+use crate::value::Value;
+
+/// A single entry of a raw IFD.
+pub(crate) struct RawEntry {
+    pub tag: u16,
+    pub format: u16,
+    pub components: u32,
+    pub data: Vec<u8>,
+    pub byte_order: ByteOrder,
+}
+
+impl RawEntry {
+    /// Decode the value of the entry, if its format is known.
+    pub fn value(&self) -> Option<Value> {
+        let data_type = DataType::try_from(self.format as u32).ok()?;
+        Value::extract(
+            &self.data,
+            data_type,
+            self.components as usize,
+            self.byte_order,
+        )
+        .ok()
+    }
+}
+
+/// The entries of a raw IFD.
+#[derive(Default)]
+pub(crate) struct RawIfd {
+    pub entries: Vec<RawEntry>,
+}
+
+impl RawIfd {
+    /// Read the IFD at `offset` in `buffer`. Value offsets are relative to
+    /// `base`, which lies before the buffer if it is negative. Entries whose
+    /// values lie outside of the buffer are skipped.
+    pub fn parse(
+        buffer: &[u8],
+        offset: usize,
+        base: isize,
+        byte_order: ByteOrder,
+    ) -> Option<RawIfd> {
+        let count = read_u16(buffer, offset, byte_order)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for index in 0..count {
+            let position = offset + 2 + index * 12;
+            let tag = read_u16(buffer, position, byte_order)?;
+            let format = read_u16(buffer, position + 2, byte_order)?;
+            let components = read_u32(buffer, position + 4, byte_order)?;
+            let size = match DataType::try_from(format as u32) {
+                Ok(data_type) => data_type.size().checked_mul(components as usize),
+                Err(_) => continue,
+            };
+            let Some(size) = size else { continue };
+            let value_offset = if size <= 4 {
+                Some(position + 8)
+            } else {
+                isize::try_from(read_u32(buffer, position + 8, byte_order)?)
+                    .ok()
+                    .and_then(|offset| offset.checked_add(base))
+                    .and_then(|offset| usize::try_from(offset).ok())
+            };
+            let Some(value_offset) = value_offset else {
+                continue;
+            };
+            let Some(data) = value_offset
+                .checked_add(size)
+                .and_then(|end| buffer.get(value_offset..end))
+            else {
+                continue;
+            };
+            entries.push(RawEntry {
+                tag,
+                format,
+                components,
+                data: data.to_vec(),
+                byte_order,
+            });
+        }
+        Some(RawIfd { entries })
+    }
+
+    /// Return the entry with the given tag, if any.
+    pub fn get(&self, tag: u16) -> Option<&RawEntry> {
+        self.entries.iter().find(|entry| entry.tag == tag)
+    }
+}
+
+/// Return the position of the value of the entry with the given tag in the IFD
+/// at `offset`, if the IFD has such an entry.
+pub(crate) fn value_offset(
+    buffer: &[u8],
+    offset: usize,
+    tag: u16,
+    byte_order: ByteOrder,
+) -> Option<usize> {
+    let count = read_u16(buffer, offset, byte_order)? as usize;
+    let position = (0..count)
+        .map(|index| offset + 2 + index * 12)
+        .find(|&position| read_u16(buffer, position, byte_order) == Some(tag))?;
+    let format = read_u16(buffer, position + 2, byte_order)?;
+    let components = read_u32(buffer, position + 4, byte_order)?;
+    let size = DataType::try_from(format as u32)
+        .ok()?
+        .size()
+        .checked_mul(components as usize)?;
+    if size <= 4 {
+        Some(position + 8)
+    } else {
+        Some(read_u32(buffer, position + 8, byte_order)? as usize)
+    }
+}
+
+/// Read a byte order mark (`II` or `MM`).
+pub(crate) fn read_byte_order(buffer: &[u8], offset: usize) -> Option<ByteOrder> {
+    match buffer.get(offset..offset + 2)? {
+        b"II" => Some(ByteOrder::LittleEndian),
+        b"MM" => Some(ByteOrder::BigEndian),
+        _ => None,
+    }
+}
+
+pub(crate) fn read_u16(buffer: &[u8], offset: usize, byte_order: ByteOrder) -> Option<u16> {
+    let bytes: [u8; 2] = buffer
+        .get(offset..offset.checked_add(2)?)?
+        .try_into()
+        .ok()?;
+    Some(match byte_order {
+        ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+    })
+}
+
+pub(crate) fn read_u32(buffer: &[u8], offset: usize, byte_order: ByteOrder) -> Option<u32> {
+    let bytes: [u8; 4] = buffer
+        .get(offset..offset.checked_add(4)?)?
+        .try_into()
+        .ok()?;
+    Some(match byte_order {
+        ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+    })
+}
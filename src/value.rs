@@ -2,9 +2,7 @@
 // Copyright (c) 2016 David Cuddeback
 //
 use crate::bits::*;
-use libc::{self, c_char};
 use libexif_sys::*;
-use std::ffi::CString;
 use std::fmt::{self, Display, Formatter};
 use std::mem;
 
@@ -25,6 +23,7 @@ use std::mem;
 /// assert_eq!(100, ratio.denominator());
 /// ```
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct Rational<T>(pub T, pub T);
 
@@ -40,6 +39,24 @@ impl<T: Copy> Rational<T> {
     }
 }
 
+impl<T: Copy + Into<f64>> Rational<T> {
+    /// Returns the value of the fraction, or `None` if the denominator is
+    /// zero.
+    ///
+    /// ```
+    /// assert_eq!(Some(0.42), libexif::Rational(42u32, 100).to_f64());
+    /// assert_eq!(None, libexif::Rational(42u32, 0).to_f64());
+    /// ```
+    pub fn to_f64(&self) -> Option<f64> {
+        let denominator: f64 = self.denominator().into();
+        if denominator == 0.0 {
+            None
+        } else {
+            Some(self.numerator().into() / denominator)
+        }
+    }
+}
+
 impl<T: Display + Copy> Display for Rational<T> {
     fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
         fmt.write_fmt(format_args!("{}/{}", self.numerator(), self.denominator()))
@@ -52,7 +69,8 @@ impl<T: Display + Copy> Display for Rational<T> {
 /// [`DataType`](enum.DataType.html). Each variant (except for `Text`) contains
 /// a `Vec` with a length equal to the number of components in the corresponding
 /// [`Entry`](struct.Entry.html).
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     /// Value interpreted as a string.
     Text(String),
@@ -77,84 +95,207 @@ pub enum Value {
 }
 
 impl Value {
+    /// Return the [`DataType`](enum.DataType.html) corresponding to the value.
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Value::Text(_) => DataType::Text,
+            Value::U8(_) => DataType::U8,
+            Value::I8(_) => DataType::I8,
+            Value::U16(_) => DataType::U16,
+            Value::I16(_) => DataType::I16,
+            Value::U32(_) => DataType::U32,
+            Value::I32(_) => DataType::I32,
+            Value::URational(_) => DataType::URational,
+            Value::IRational(_) => DataType::IRational,
+            Value::Undefined(_) => DataType::Undefined,
+        }
+    }
+
+    /// Return the number of components needed to store the value.
+    ///
+    /// Text is stored with a terminating null byte, which counts as a
+    /// component.
+    pub fn components(&self) -> usize {
+        match self {
+            Value::Text(v) => v.len() + 1,
+            Value::U8(v) => v.len(),
+            Value::I8(v) => v.len(),
+            Value::U16(v) => v.len(),
+            Value::I16(v) => v.len(),
+            Value::U32(v) => v.len(),
+            Value::I32(v) => v.len(),
+            Value::URational(v) => v.len(),
+            Value::IRational(v) => v.len(),
+            Value::Undefined(v) => v.len(),
+        }
+    }
+
+    pub(crate) fn encode(&self, byte_order: ByteOrder) -> Vec<u8> {
+        match self {
+            Value::Text(v) => {
+                let mut bytes = Vec::with_capacity(v.len() + 1);
+                bytes.extend_from_slice(v.as_bytes());
+                bytes.push(0);
+                bytes
+            }
+            Value::U8(v) => encode_vec(v, byte_order, set_u8),
+            Value::I8(v) => encode_vec(v, byte_order, set_i8),
+            Value::U16(v) => encode_vec(v, byte_order, exif_set_short),
+            Value::I16(v) => encode_vec(v, byte_order, exif_set_sshort),
+            Value::U32(v) => encode_vec(v, byte_order, exif_set_long),
+            Value::I32(v) => encode_vec(v, byte_order, exif_set_slong),
+            Value::URational(v) => encode_vec(v, byte_order, set_urational),
+            Value::IRational(v) => encode_vec(v, byte_order, set_irational),
+            Value::Undefined(v) => v.clone(),
+        }
+    }
+
+    /// Decode the raw data of an entry, which must hold exactly `components`
+    /// components of the given data type.
     pub(crate) fn extract(
         raw_data: &[u8],
         data_type: DataType,
         components: usize,
         byte_order: ByteOrder,
-    ) -> Self {
-        assert_eq!(raw_data.len(), data_type.size() * components);
-        match data_type {
-            DataType::Text => Value::Text(extract_text(raw_data, components, byte_order)),
-            DataType::U8 => Value::U8(extract_vec::<u8>(raw_data, components, byte_order, get_u8)),
-            DataType::I8 => Value::I8(extract_vec::<i8>(raw_data, components, byte_order, get_i8)),
-            DataType::U16 => Value::U16(extract_vec::<u16>(
-                raw_data,
-                components,
-                byte_order,
-                exif_get_short,
-            )),
-            DataType::I16 => Value::I16(extract_vec::<i16>(
-                raw_data,
-                components,
-                byte_order,
-                exif_get_sshort,
-            )),
-            DataType::U32 => Value::U32(extract_vec::<u32>(
-                raw_data,
-                components,
-                byte_order,
-                exif_get_long,
-            )),
-            DataType::I32 => Value::I32(extract_vec::<i32>(
-                raw_data,
-                components,
-                byte_order,
-                exif_get_slong,
-            )),
+    ) -> Result<Self, SizeMismatch> {
+        match Value::extract_lenient(raw_data, data_type, components, byte_order) {
+            (value, None) => Ok(value),
+            (_, Some(mismatch)) => Err(mismatch),
+        }
+    }
+
+    /// Decode as many whole components of the raw data as are present, up to
+    /// `components`, along with the size mismatch if the raw data does not
+    /// hold exactly `components` components.
+    pub(crate) fn extract_lenient(
+        raw_data: &[u8],
+        data_type: DataType,
+        components: usize,
+        byte_order: ByteOrder,
+    ) -> (Self, Option<SizeMismatch>) {
+        let expected_size = data_type.size().saturating_mul(components);
+        let mismatch = if raw_data.len() == expected_size {
+            None
+        } else {
+            Some(SizeMismatch {
+                expected_size,
+                actual_size: raw_data.len(),
+            })
+        };
+        let components = components.min(raw_data.len() / data_type.size());
+        let raw_data = &raw_data[..components * data_type.size()];
+        let value = match data_type {
+            DataType::Text => Value::Text(extract_text(raw_data)),
+            DataType::U8 => Value::U8(extract_vec::<u8>(raw_data, byte_order, get_u8)),
+            DataType::I8 => Value::I8(extract_vec::<i8>(raw_data, byte_order, get_i8)),
+            DataType::U16 => Value::U16(extract_vec::<u16>(raw_data, byte_order, exif_get_short)),
+            DataType::I16 => Value::I16(extract_vec::<i16>(raw_data, byte_order, exif_get_sshort)),
+            DataType::U32 => Value::U32(extract_vec::<u32>(raw_data, byte_order, exif_get_long)),
+            DataType::I32 => Value::I32(extract_vec::<i32>(raw_data, byte_order, exif_get_slong)),
             DataType::URational => Value::URational(extract_vec::<Rational<u32>>(
                 raw_data,
-                components,
                 byte_order,
                 get_urational,
             )),
             DataType::IRational => Value::IRational(extract_vec::<Rational<i32>>(
                 raw_data,
-                components,
                 byte_order,
                 get_irational,
             )),
-            DataType::Undefined => {
-                Value::Undefined(extract_vec::<u8>(raw_data, components, byte_order, get_u8))
+            DataType::Undefined => Value::Undefined(raw_data.to_vec()),
+        };
+        (value, mismatch)
+    }
+}
+
+impl Display for Value {
+    /// Format the value's components separated by commas. Text is written as
+    /// is and undefined bytes are written in hexadecimal.
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Value::Text(v) => fmt.write_str(v),
+            Value::U8(v) => write_list(fmt, v),
+            Value::I8(v) => write_list(fmt, v),
+            Value::U16(v) => write_list(fmt, v),
+            Value::I16(v) => write_list(fmt, v),
+            Value::U32(v) => write_list(fmt, v),
+            Value::I32(v) => write_list(fmt, v),
+            Value::URational(v) => write_list(fmt, v),
+            Value::IRational(v) => write_list(fmt, v),
+            Value::Undefined(v) => {
+                for (index, byte) in v.iter().enumerate() {
+                    if index > 0 {
+                        fmt.write_str(" ")?;
+                    }
+                    write!(fmt, "{:02x}", byte)?;
+                }
+                Ok(())
             }
         }
     }
 }
 
-fn extract_text(raw_data: &[u8], components: usize, byte_order: ByteOrder) -> String {
-    let mut vec = extract_vec::<u8>(raw_data, components, byte_order, get_u8);
-    let cstring = unsafe {
-        let len = libc::strlen(vec.as_ptr() as *const c_char);
-        vec.set_len(len);
-        CString::from_vec_unchecked(vec)
-    };
-    cstring.to_string_lossy().into_owned()
+fn write_list<T: Display>(fmt: &mut Formatter, values: &[T]) -> Result<(), fmt::Error> {
+    for (index, value) in values.iter().enumerate() {
+        if index > 0 {
+            fmt.write_str(", ")?;
+        }
+        value.fmt(fmt)?;
+    }
+    Ok(())
 }
 
+/// Discrepancy between the size of an entry's raw data and the size implied
+/// by its data type and number of components.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct SizeMismatch {
+    /// Size in bytes implied by the data type and number of components.
+    pub expected_size: usize,
+    /// Actual size in bytes of the raw data.
+    pub actual_size: usize,
+}
+
+impl Display for SizeMismatch {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(
+            fmt,
+            "expected {} bytes of data, found {}",
+            self.expected_size, self.actual_size
+        )
+    }
+}
+
+/// Decode text up to the first null byte, or all of the data if there is none.
+fn extract_text(raw_data: &[u8]) -> String {
+    let len = raw_data
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(raw_data.len());
+    String::from_utf8_lossy(&raw_data[..len]).into_owned()
+}
+
+/// Decode the raw data, which must hold a whole number of components.
 fn extract_vec<T>(
     raw_data: &[u8],
-    components: usize,
     byte_order: ByteOrder,
     get: unsafe extern "C" fn(*const u8, ExifByteOrder) -> T,
 ) -> Vec<T> {
-    assert_eq!(raw_data.len(), mem::size_of::<T>() * components);
-    let mut values = Vec::with_capacity(components);
-    values.extend(
-        raw_data
-            .chunks(mem::size_of::<T>())
-            .map(|chunk| unsafe { get(chunk.as_ptr(), byte_order.into()) }),
-    );
-    values
+    raw_data
+        .chunks_exact(mem::size_of::<T>())
+        .map(|chunk| unsafe { get(chunk.as_ptr(), byte_order.into()) })
+        .collect()
+}
+
+fn encode_vec<T: Copy>(
+    values: &[T],
+    byte_order: ByteOrder,
+    set: unsafe extern "C" fn(*mut u8, ExifByteOrder, T),
+) -> Vec<u8> {
+    let mut bytes = vec![0u8; mem::size_of_val(values)];
+    for (chunk, value) in bytes.chunks_mut(mem::size_of::<T>()).zip(values) {
+        unsafe { set(chunk.as_mut_ptr(), byte_order.into(), *value) };
+    }
+    bytes
 }
 
 unsafe extern "C" fn get_u8(buf: *const u8, _byte_order: ExifByteOrder) -> u8 {
@@ -174,3 +315,27 @@ unsafe extern "C" fn get_irational(buf: *const u8, byte_order: ExifByteOrder) ->
     let rational = exif_get_srational(buf, byte_order);
     Rational(rational.numerator, rational.denominator)
 }
+
+unsafe extern "C" fn set_u8(buf: *mut u8, _byte_order: ExifByteOrder, value: u8) {
+    *buf = value;
+}
+
+unsafe extern "C" fn set_i8(buf: *mut u8, _byte_order: ExifByteOrder, value: i8) {
+    *buf = value as u8;
+}
+
+unsafe extern "C" fn set_urational(buf: *mut u8, byte_order: ExifByteOrder, value: Rational<u32>) {
+    let rational = ExifRational {
+        numerator: value.0,
+        denominator: value.1,
+    };
+    exif_set_rational(buf, byte_order, rational);
+}
+
+unsafe extern "C" fn set_irational(buf: *mut u8, byte_order: ExifByteOrder, value: Rational<i32>) {
+    let rational = ExifSRational {
+        numerator: value.0,
+        denominator: value.1,
+    };
+    exif_set_srational(buf, byte_order, rational);
+}
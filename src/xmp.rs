@@ -0,0 +1,1252 @@
+//! Conversion to and from XMP packets, following the mapping of EXIF tags to
+//! the `tiff`, `exif` and `aux` namespaces of the XMP specification.
+use crate::bits::*;
+use crate::data::Data;
+use crate::entry::allows_data_type;
+use crate::gps::{read_coordinate, system_time_from_civil, write_coordinate, write_timestamp};
+use crate::tag::Tag;
+use crate::text::{format_offset, parse_offset, parse_rational, read_comment, ASCII_COMMENT};
+use crate::value::{Rational, Value};
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+use std::fmt::{self, Display, Formatter};
+use std::time::SystemTime;
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Prefixes and URIs of the namespaces of the properties.
+const NAMESPACES: [(&str, &str); 5] = [
+    ("tiff", "http://ns.adobe.com/tiff/1.0/"),
+    ("exif", "http://ns.adobe.com/exif/1.0/"),
+    ("aux", "http://ns.adobe.com/exif/1.0/aux/"),
+    ("xmp", "http://ns.adobe.com/xap/1.0/"),
+    ("dc", "http://purl.org/dc/elements/1.1/"),
+];
+
+/// Denominator of the fraction of minutes in coordinates.
+const MINUTE_DENOMINATOR: u64 = 1_000_000;
+
+/// How the value of a tag is represented in XMP.
+#[derive(Clone, Copy)]
+enum Kind {
+    Text,
+    /// Text in the default language of a language alternative.
+    LangAlt,
+    /// Text separated by semicolons, as an ordered array.
+    TextSeq,
+    Integer,
+    /// Integers as an ordered array.
+    IntegerSeq,
+    /// A rational written as `n/d`.
+    Rational,
+    /// Rationals as an ordered array.
+    RationalSeq,
+    /// Rationals separated by spaces.
+    RationalList,
+    /// Undefined ASCII digits, such as `0230`.
+    Version,
+    /// Text of a `UserComment`, as a language alternative.
+    Comment,
+    /// Date and time, with the tags of the sub-seconds and the offset from
+    /// UTC in the EXIF IFD.
+    DateTime {
+        sub_sec: Tag,
+        offset: Tag,
+    },
+    /// Coordinate written as `DDD,MM.mmmmmmk`, with the tag of its reference
+    /// and the references of positive and negative values.
+    Coordinate {
+        reference: Tag,
+        hemispheres: [&'static str; 2],
+        max: f64,
+    },
+    /// Version written as `2.2.0.0`.
+    GpsVersion,
+    /// Combination of `GPSDateStamp` and `GPSTimeStamp` in UTC.
+    GpsTimeStamp,
+    /// Structure of the bit fields of `Flash`.
+    Flash,
+}
+
+/// Tags of an IFD that have an XMP equivalent and their qualified XMP names.
+type Properties = &'static [(Tag, &'static str, Kind)];
+
+const PROPERTIES: [(IFD, Properties); 3] = [
+    (IFD::Image, IMAGE_PROPERTIES),
+    (IFD::EXIF, EXIF_PROPERTIES),
+    (IFD::GPS, GPS_PROPERTIES),
+];
+
+const IMAGE_PROPERTIES: Properties = &[
+    (Tag::IMAGE_WIDTH, "tiff:ImageWidth", Kind::Integer),
+    (Tag::IMAGE_LENGTH, "tiff:ImageLength", Kind::Integer),
+    (Tag::BITS_PER_SAMPLE, "tiff:BitsPerSample", Kind::IntegerSeq),
+    (Tag::COMPRESSION, "tiff:Compression", Kind::Integer),
+    (
+        Tag::PHOTOMETRIC_INTERPRETATION,
+        "tiff:PhotometricInterpretation",
+        Kind::Integer,
+    ),
+    (Tag::ORIENTATION, "tiff:Orientation", Kind::Integer),
+    (
+        Tag::SAMPLES_PER_PIXEL,
+        "tiff:SamplesPerPixel",
+        Kind::Integer,
+    ),
+    (
+        Tag::PLANAR_CONFIGURATION,
+        "tiff:PlanarConfiguration",
+        Kind::Integer,
+    ),
+    (
+        Tag::YCBCR_SUB_SAMPLING,
+        "tiff:YCbCrSubSampling",
+        Kind::IntegerSeq,
+    ),
+    (
+        Tag::YCBCR_POSITIONING,
+        "tiff:YCbCrPositioning",
+        Kind::Integer,
+    ),
+    (Tag::X_RESOLUTION, "tiff:XResolution", Kind::Rational),
+    (Tag::Y_RESOLUTION, "tiff:YResolution", Kind::Rational),
+    (Tag::RESOLUTION_UNIT, "tiff:ResolutionUnit", Kind::Integer),
+    (
+        Tag::TRANSFER_FUNCTION,
+        "tiff:TransferFunction",
+        Kind::IntegerSeq,
+    ),
+    (Tag::WHITE_POINT, "tiff:WhitePoint", Kind::RationalSeq),
+    (
+        Tag::PRIMARY_CHROMATICITIES,
+        "tiff:PrimaryChromaticities",
+        Kind::RationalSeq,
+    ),
+    (
+        Tag::YCBCR_COEFFICIENTS,
+        "tiff:YCbCrCoefficients",
+        Kind::RationalSeq,
+    ),
+    (
+        Tag::REFERENCE_BLACK_WHITE,
+        "tiff:ReferenceBlackWhite",
+        Kind::RationalSeq,
+    ),
+    (
+        Tag::DATE_TIME,
+        "xmp:ModifyDate",
+        Kind::DateTime {
+            sub_sec: Tag::SUB_SEC_TIME,
+            offset: Tag::OFFSET_TIME,
+        },
+    ),
+    (Tag::IMAGE_DESCRIPTION, "dc:description", Kind::LangAlt),
+    (Tag::MAKE, "tiff:Make", Kind::Text),
+    (Tag::MODEL, "tiff:Model", Kind::Text),
+    (Tag::SOFTWARE, "tiff:Software", Kind::Text),
+    (Tag::ARTIST, "dc:creator", Kind::TextSeq),
+    (Tag::COPYRIGHT, "dc:rights", Kind::LangAlt),
+];
+
+const EXIF_PROPERTIES: Properties = &[
+    (Tag::EXIF_VERSION, "exif:ExifVersion", Kind::Version),
+    (
+        Tag::FLASH_PIX_VERSION,
+        "exif:FlashpixVersion",
+        Kind::Version,
+    ),
+    (Tag::COLOR_SPACE, "exif:ColorSpace", Kind::Integer),
+    (
+        Tag::COMPONENTS_CONFIGURATION,
+        "exif:ComponentsConfiguration",
+        Kind::IntegerSeq,
+    ),
+    (
+        Tag::COMPRESSED_BITS_PER_PIXEL,
+        "exif:CompressedBitsPerPixel",
+        Kind::Rational,
+    ),
+    (
+        Tag::PIXEL_X_DIMENSION,
+        "exif:PixelXDimension",
+        Kind::Integer,
+    ),
+    (
+        Tag::PIXEL_Y_DIMENSION,
+        "exif:PixelYDimension",
+        Kind::Integer,
+    ),
+    (Tag::USER_COMMENT, "exif:UserComment", Kind::Comment),
+    (Tag::RELATED_SOUND_FILE, "exif:RelatedSoundFile", Kind::Text),
+    (
+        Tag::DATE_TIME_ORIGINAL,
+        "exif:DateTimeOriginal",
+        Kind::DateTime {
+            sub_sec: Tag::SUB_SEC_TIME_ORIGINAL,
+            offset: Tag::OFFSET_TIME_ORIGINAL,
+        },
+    ),
+    (
+        Tag::DATE_TIME_DIGITIZED,
+        "exif:DateTimeDigitized",
+        Kind::DateTime {
+            sub_sec: Tag::SUB_SEC_TIME_DIGITIZED,
+            offset: Tag::OFFSET_TIME_DIGITIZED,
+        },
+    ),
+    (Tag::EXPOSURE_TIME, "exif:ExposureTime", Kind::Rational),
+    (Tag::FNUMBER, "exif:FNumber", Kind::Rational),
+    (Tag::EXPOSURE_PROGRAM, "exif:ExposureProgram", Kind::Integer),
+    (
+        Tag::SPECTRAL_SENSITIVITY,
+        "exif:SpectralSensitivity",
+        Kind::Text,
+    ),
+    (
+        Tag::ISO_SPEED_RATINGS,
+        "exif:ISOSpeedRatings",
+        Kind::IntegerSeq,
+    ),
+    (
+        Tag::SHUTTER_SPEED_VALUE,
+        "exif:ShutterSpeedValue",
+        Kind::Rational,
+    ),
+    (Tag::APERTURE_VALUE, "exif:ApertureValue", Kind::Rational),
+    (
+        Tag::BRIGHTNESS_VALUE,
+        "exif:BrightnessValue",
+        Kind::Rational,
+    ),
+    (
+        Tag::EXPOSURE_BIAS_VALUE,
+        "exif:ExposureBiasValue",
+        Kind::Rational,
+    ),
+    (
+        Tag::MAX_APERTURE_VALUE,
+        "exif:MaxApertureValue",
+        Kind::Rational,
+    ),
+    (
+        Tag::SUBJECT_DISTANCE,
+        "exif:SubjectDistance",
+        Kind::Rational,
+    ),
+    (Tag::METERING_MODE, "exif:MeteringMode", Kind::Integer),
+    (Tag::LIGHT_SOURCE, "exif:LightSource", Kind::Integer),
+    (Tag::FLASH, "exif:Flash", Kind::Flash),
+    (Tag::FOCAL_LENGTH, "exif:FocalLength", Kind::Rational),
+    (Tag::SUBJECT_AREA, "exif:SubjectArea", Kind::IntegerSeq),
+    (Tag::FLASH_ENERGY, "exif:FlashEnergy", Kind::Rational),
+    (
+        Tag::FOCAL_PLANE_X_RESOLUTION,
+        "exif:FocalPlaneXResolution",
+        Kind::Rational,
+    ),
+    (
+        Tag::FOCAL_PLANE_Y_RESOLUTION,
+        "exif:FocalPlaneYResolution",
+        Kind::Rational,
+    ),
+    (
+        Tag::FOCAL_PLANE_RESOLUTION_UNIT,
+        "exif:FocalPlaneResolutionUnit",
+        Kind::Integer,
+    ),
+    (
+        Tag::SUBJECT_LOCATION,
+        "exif:SubjectLocation",
+        Kind::IntegerSeq,
+    ),
+    (Tag::EXPOSURE_INDEX, "exif:ExposureIndex", Kind::Rational),
+    (Tag::SENSING_METHOD, "exif:SensingMethod", Kind::Integer),
+    (Tag::FILE_SOURCE, "exif:FileSource", Kind::Integer),
+    (Tag::SCENE_TYPE, "exif:SceneType", Kind::Integer),
+    (Tag::CUSTOM_RENDERED, "exif:CustomRendered", Kind::Integer),
+    (Tag::EXPOSURE_MODE, "exif:ExposureMode", Kind::Integer),
+    (Tag::WHITE_BALANCE, "exif:WhiteBalance", Kind::Integer),
+    (
+        Tag::DIGITAL_ZOOM_RATIO,
+        "exif:DigitalZoomRatio",
+        Kind::Rational,
+    ),
+    (
+        Tag::FOCAL_LENGTH_IN_35MM_FILM,
+        "exif:FocalLengthIn35mmFilm",
+        Kind::Integer,
+    ),
+    (
+        Tag::SCENE_CAPTURE_TYPE,
+        "exif:SceneCaptureType",
+        Kind::Integer,
+    ),
+    (Tag::GAIN_CONTROL, "exif:GainControl", Kind::Integer),
+    (Tag::CONTRAST, "exif:Contrast", Kind::Integer),
+    (Tag::SATURATION, "exif:Saturation", Kind::Integer),
+    (Tag::SHARPNESS, "exif:Sharpness", Kind::Integer),
+    (
+        Tag::SUBJECT_DISTANCE_RANGE,
+        "exif:SubjectDistanceRange",
+        Kind::Integer,
+    ),
+    (Tag::IMAGE_UNIQUE_ID, "exif:ImageUniqueID", Kind::Text),
+    (Tag::CAMERA_OWNER_NAME, "aux:OwnerName", Kind::Text),
+    (Tag::BODY_SERIAL_NUMBER, "aux:SerialNumber", Kind::Text),
+    (Tag::LENS_SPECIFICATION, "aux:LensInfo", Kind::RationalList),
+    (Tag::LENS_MODEL, "aux:Lens", Kind::Text),
+    (Tag::LENS_SERIAL_NUMBER, "aux:LensSerialNumber", Kind::Text),
+];
+
+const GPS_PROPERTIES: Properties = &[
+    (Tag::GPS_VERSION_ID, "exif:GPSVersionID", Kind::GpsVersion),
+    (
+        Tag::GPS_LATITUDE,
+        "exif:GPSLatitude",
+        Kind::Coordinate {
+            reference: Tag::GPS_LATITUDE_REF,
+            hemispheres: ["N", "S"],
+            max: 90.0,
+        },
+    ),
+    (
+        Tag::GPS_LONGITUDE,
+        "exif:GPSLongitude",
+        Kind::Coordinate {
+            reference: Tag::GPS_LONGITUDE_REF,
+            hemispheres: ["E", "W"],
+            max: 180.0,
+        },
+    ),
+    (Tag::GPS_ALTITUDE_REF, "exif:GPSAltitudeRef", Kind::Integer),
+    (Tag::GPS_ALTITUDE, "exif:GPSAltitude", Kind::Rational),
+    (Tag::GPS_TIME_STAMP, "exif:GPSTimeStamp", Kind::GpsTimeStamp),
+    (Tag::GPS_SATELLITES, "exif:GPSSatellites", Kind::Text),
+    (Tag::GPS_STATUS, "exif:GPSStatus", Kind::Text),
+    (Tag::GPS_MEASURE_MODE, "exif:GPSMeasureMode", Kind::Text),
+    (Tag::GPS_DOP, "exif:GPSDOP", Kind::Rational),
+    (Tag::GPS_SPEED_REF, "exif:GPSSpeedRef", Kind::Text),
+    (Tag::GPS_SPEED, "exif:GPSSpeed", Kind::Rational),
+    (Tag::GPS_TRACK_REF, "exif:GPSTrackRef", Kind::Text),
+    (Tag::GPS_TRACK, "exif:GPSTrack", Kind::Rational),
+    (
+        Tag::GPS_IMG_DIRECTION_REF,
+        "exif:GPSImgDirectionRef",
+        Kind::Text,
+    ),
+    (
+        Tag::GPS_IMG_DIRECTION,
+        "exif:GPSImgDirection",
+        Kind::Rational,
+    ),
+    (Tag::GPS_MAP_DATUM, "exif:GPSMapDatum", Kind::Text),
+    (
+        Tag::GPS_DEST_LATITUDE,
+        "exif:GPSDestLatitude",
+        Kind::Coordinate {
+            reference: Tag::GPS_DEST_LATITUDE_REF,
+            hemispheres: ["N", "S"],
+            max: 90.0,
+        },
+    ),
+    (
+        Tag::GPS_DEST_LONGITUDE,
+        "exif:GPSDestLongitude",
+        Kind::Coordinate {
+            reference: Tag::GPS_DEST_LONGITUDE_REF,
+            hemispheres: ["E", "W"],
+            max: 180.0,
+        },
+    ),
+    (
+        Tag::GPS_DEST_BEARING_REF,
+        "exif:GPSDestBearingRef",
+        Kind::Text,
+    ),
+    (Tag::GPS_DEST_BEARING, "exif:GPSDestBearing", Kind::Rational),
+    (
+        Tag::GPS_DEST_DISTANCE_REF,
+        "exif:GPSDestDistanceRef",
+        Kind::Text,
+    ),
+    (
+        Tag::GPS_DEST_DISTANCE,
+        "exif:GPSDestDistance",
+        Kind::Rational,
+    ),
+    (Tag::GPS_DIFFERENTIAL, "exif:GPSDifferential", Kind::Integer),
+];
+
+/// Value of an XMP property.
+#[derive(Debug, PartialEq)]
+enum Property {
+    Simple(String),
+    /// Items of an ordered or unordered array.
+    Seq(Vec<String>),
+    /// Default item of a language alternative.
+    Alt(String),
+    /// Names and values of the fields of a structure, which share the
+    /// namespace of the property.
+    Struct(Vec<(String, String)>),
+}
+
+impl Property {
+    /// Return the text of a simple value, of a language alternative or of an
+    /// array with a single item.
+    fn text(&self) -> Option<&str> {
+        match self {
+            Property::Simple(text) | Property::Alt(text) => Some(text),
+            Property::Seq(items) if items.len() == 1 => Some(&items[0]),
+            _ => None,
+        }
+    }
+
+    /// Return the whitespace-separated tokens of all items.
+    fn tokens(&self) -> Vec<&str> {
+        match self {
+            Property::Simple(text) | Property::Alt(text) => text.split_whitespace().collect(),
+            Property::Seq(items) => items
+                .iter()
+                .flat_map(|item| item.split_whitespace())
+                .collect(),
+            Property::Struct(_) => Vec::new(),
+        }
+    }
+
+    /// Return the value as written in the packet, for error messages.
+    fn describe(&self) -> String {
+        match self {
+            Property::Simple(text) | Property::Alt(text) => text.clone(),
+            Property::Seq(items) => items.join(", "),
+            Property::Struct(fields) => fields
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    fn field(&self, name: &str) -> Option<&str> {
+        match self {
+            Property::Struct(fields) => fields
+                .iter()
+                .find(|(other, _)| other == name)
+                .map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Build an XMP packet from the entries that have an XMP equivalent.
+pub(crate) fn export(data: &Data) -> String {
+    let mut properties = Vec::new();
+    if let Ok(byte_order) = data.byte_order() {
+        let value = |ifd: IFD, tag: Tag| data.get(ifd, tag)?.value(byte_order).ok();
+        for (ifd, tag, name, kind) in property_table() {
+            if let Some(property) = read_property(ifd, tag, kind, &value) {
+                properties.push((name, property));
+            }
+        }
+    }
+    Packet(&properties).to_string()
+}
+
+/// Apply the EXIF properties of all descriptions in an XMP packet. Other
+/// properties are ignored.
+pub(crate) fn import(data: &mut Data, packet: &str) -> Result<(), super::Error> {
+    let root = parse(packet)?;
+    let mut properties = Vec::new();
+    collect(&root, &mut properties);
+    // convert every property before applying any, so that the data is left
+    // unchanged if one of them is invalid
+    let mut changes = Vec::new();
+    for (namespace, local_name, property) in properties {
+        let Some((ifd, tag, name, kind)) =
+            property_table().find(|(_, _, name, _)| is_named(name, namespace, local_name))
+        else {
+            continue;
+        };
+        let converted = convert_property(data, ifd, tag, kind, &property).map_err(|reason| {
+            super::Error::InvalidXmp(format!(
+                "cannot apply {} {:?}: {}",
+                name,
+                property.describe(),
+                reason
+            ))
+        })?;
+        changes.extend(converted);
+    }
+    for (ifd, tag, value) in changes {
+        let mut content = data.content_mut(ifd);
+        match value {
+            Some(value) => {
+                content.insert_value(tag, value)?;
+            }
+            None => {
+                content.remove(tag);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn property_table() -> impl Iterator<Item = (IFD, Tag, &'static str, Kind)> {
+    PROPERTIES.iter().flat_map(|(ifd, properties)| {
+        properties
+            .iter()
+            .map(move |(tag, name, kind)| (*ifd, *tag, *name, *kind))
+    })
+}
+
+/// Returns `true` if a qualified name such as `exif:FNumber` refers to the
+/// given namespace and local name.
+fn is_named(name: &str, namespace: &str, local_name: &str) -> bool {
+    let Some((prefix, local)) = name.split_once(':') else {
+        return false;
+    };
+    local == local_name
+        && NAMESPACES
+            .iter()
+            .any(|(other, uri)| *other == prefix && *uri == namespace)
+}
+
+fn read_property(
+    ifd: IFD,
+    tag: Tag,
+    kind: Kind,
+    value: &dyn Fn(IFD, Tag) -> Option<Value>,
+) -> Option<Property> {
+    Some(match kind {
+        Kind::Text => Property::Simple(text(value(ifd, tag)?)?),
+        Kind::LangAlt => Property::Alt(text(value(ifd, tag)?)?),
+        Kind::TextSeq => Property::Seq(
+            text(value(ifd, tag)?)?
+                .split(';')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(String::from)
+                .collect(),
+        ),
+        Kind::Integer => Property::Simple(integers(value(ifd, tag)?)?[0].to_string()),
+        Kind::IntegerSeq => Property::Seq(
+            integers(value(ifd, tag)?)?
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        ),
+        Kind::Rational => Property::Simple(rationals(value(ifd, tag)?)?.swap_remove(0)),
+        Kind::RationalSeq => Property::Seq(rationals(value(ifd, tag)?)?),
+        Kind::RationalList => Property::Simple(rationals(value(ifd, tag)?)?.join(" ")),
+        Kind::Version => match value(ifd, tag)? {
+            Value::Undefined(bytes)
+                if !bytes.is_empty() && bytes.iter().all(u8::is_ascii_digit) =>
+            {
+                Property::Simple(String::from_utf8(bytes).ok()?)
+            }
+            _ => return None,
+        },
+        Kind::Comment => {
+            Property::Alt(read_comment(&value(ifd, tag)?).filter(|comment| !comment.is_empty())?)
+        }
+        Kind::DateTime { sub_sec, offset } => {
+            let mut date = XmpDate::from_exif(&text(value(ifd, tag)?)?)?;
+            if let Some(sub_sec) = value(IFD::EXIF, sub_sec).and_then(text) {
+                date.fraction = sub_sec
+                    .trim()
+                    .chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect();
+            }
+            date.offset = value(IFD::EXIF, offset)
+                .and_then(text)
+                .and_then(|offset| parse_offset(offset.trim()));
+            Property::Simple(date.to_string())
+        }
+        Kind::Coordinate {
+            reference,
+            hemispheres,
+            ..
+        } => {
+            let negative = hemispheres[1].chars().next()?;
+            let degrees = read_coordinate(value(ifd, tag), value(ifd, reference), negative)?;
+            let minutes = (degrees.abs() * 60.0 * MINUTE_DENOMINATOR as f64).round() as u64;
+            let per_degree = 60 * MINUTE_DENOMINATOR;
+            Property::Simple(format!(
+                "{},{:02}.{:06}{}",
+                minutes / per_degree,
+                minutes % per_degree / MINUTE_DENOMINATOR,
+                minutes % MINUTE_DENOMINATOR,
+                hemispheres[usize::from(degrees < 0.0)]
+            ))
+        }
+        Kind::GpsVersion => match value(ifd, tag)? {
+            Value::U8(version) if !version.is_empty() => Property::Simple(
+                version
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("."),
+            ),
+            _ => return None,
+        },
+        Kind::GpsTimeStamp => {
+            let (year, month, day) = exif_date(&text(value(ifd, Tag::GPS_DATE_STAMP)?)?)?;
+            let Value::URational(time) = value(ifd, tag)? else {
+                return None;
+            };
+            if time.len() < 3 {
+                return None;
+            }
+            let seconds =
+                time[0].to_f64()? * 3600.0 + time[1].to_f64()? * 60.0 + time[2].to_f64()?;
+            let millis = (seconds * 1000.0).round();
+            if !(0.0..86_400_000.0).contains(&millis) {
+                return None;
+            }
+            let millis = millis as u32;
+            let date = XmpDate {
+                year,
+                month,
+                day,
+                hour: millis / 3_600_000,
+                minute: millis / 60_000 % 60,
+                second: millis / 1000 % 60,
+                fraction: format!("{:03}", millis % 1000)
+                    .trim_end_matches('0')
+                    .to_string(),
+                offset: Some(0),
+            };
+            Property::Simple(date.to_string())
+        }
+        Kind::Flash => {
+            let Value::U16(flash) = value(ifd, tag)? else {
+                return None;
+            };
+            let flash = *flash.first()?;
+            let boolean = |mask: u16| if flash & mask != 0 { "True" } else { "False" }.to_string();
+            Property::Struct(vec![
+                ("Fired".into(), boolean(0x01)),
+                ("Return".into(), ((flash >> 1) & 0x03).to_string()),
+                ("Mode".into(), ((flash >> 3) & 0x03).to_string()),
+                ("Function".into(), boolean(0x20)),
+                ("RedEyeMode".into(), boolean(0x40)),
+            ])
+        }
+    })
+}
+
+/// An entry to set, or to remove if the value is `None`.
+type Change = (IFD, Tag, Option<Value>);
+
+/// Convert a property to the entries it changes, returning the reason if the
+/// property cannot be converted.
+fn convert_property(
+    data: &Data,
+    ifd: IFD,
+    tag: Tag,
+    kind: Kind,
+    property: &Property,
+) -> Result<Vec<Change>, &'static str> {
+    let text = || property.text().ok_or("expected a single value");
+    let changes = match kind {
+        Kind::Text | Kind::LangAlt => vec![(ifd, tag, Some(Value::Text(text()?.into())))],
+        Kind::TextSeq => {
+            let text = match property {
+                Property::Seq(items) => items.join("; "),
+                _ => text()?.to_string(),
+            };
+            vec![(ifd, tag, Some(Value::Text(text)))]
+        }
+        Kind::Integer | Kind::IntegerSeq => {
+            let numbers = property
+                .tokens()
+                .into_iter()
+                .map(|token| token.parse().ok())
+                .collect::<Option<Vec<i64>>>()
+                .filter(|numbers| !numbers.is_empty())
+                .ok_or("expected whole numbers")?;
+            let value = data
+                .content(ifd)
+                .build_value(tag, DataType::U32, |data_type| {
+                    integer_value(&numbers, data_type)
+                })
+                .ok_or("numbers out of range")?;
+            vec![(ifd, tag, Some(value))]
+        }
+        Kind::Rational | Kind::RationalSeq | Kind::RationalList => {
+            let rationals = property
+                .tokens()
+                .into_iter()
+                .map(parse_rational)
+                .collect::<Option<Vec<_>>>()
+                .filter(|rationals| !rationals.is_empty())
+                .ok_or("expected rational numbers")?;
+            let value = data
+                .content(ifd)
+                .build_value(tag, DataType::URational, |data_type| {
+                    rational_value(&rationals, data_type)
+                })
+                .ok_or("numbers out of range")?;
+            vec![(ifd, tag, Some(value))]
+        }
+        Kind::Version => {
+            let version = text()?.trim();
+            if version.is_empty() || !version.bytes().all(|b| b.is_ascii_digit()) {
+                return Err("expected a version of digits only");
+            }
+            vec![(
+                ifd,
+                tag,
+                Some(Value::Undefined(version.as_bytes().to_vec())),
+            )]
+        }
+        Kind::Comment => {
+            let mut comment = ASCII_COMMENT.to_vec();
+            comment.extend_from_slice(text()?.as_bytes());
+            vec![(ifd, tag, Some(Value::Undefined(comment)))]
+        }
+        Kind::DateTime { sub_sec, offset } => {
+            let date = XmpDate::parse(text()?).ok_or("expected a date")?;
+            vec![
+                (ifd, tag, Some(Value::Text(date.to_exif()))),
+                (
+                    IFD::EXIF,
+                    sub_sec,
+                    (!date.fraction.is_empty()).then(|| Value::Text(date.fraction.clone())),
+                ),
+                (
+                    IFD::EXIF,
+                    offset,
+                    date.offset
+                        .map(|minutes| Value::Text(format_offset(minutes))),
+                ),
+            ]
+        }
+        Kind::Coordinate {
+            reference,
+            hemispheres,
+            max,
+        } => {
+            let degrees =
+                parse_coordinate(text()?, hemispheres).ok_or("expected degrees and minutes")?;
+            let (hemisphere, dms) = write_coordinate(degrees, max, hemispheres)
+                .map_err(|_| "coordinate out of range")?;
+            vec![(ifd, reference, Some(hemisphere)), (ifd, tag, Some(dms))]
+        }
+        Kind::GpsVersion => {
+            let version = text()?
+                .trim()
+                .split('.')
+                .map(|part| part.parse().ok())
+                .collect::<Option<Vec<u8>>>()
+                .ok_or("expected a version of numbers separated by dots")?;
+            vec![(ifd, tag, Some(Value::U8(version)))]
+        }
+        Kind::GpsTimeStamp => {
+            let timestamp = XmpDate::parse(text()?).ok_or("expected a date")?;
+            let (date, time) = timestamp
+                .to_system_time()
+                .and_then(|timestamp| write_timestamp(timestamp).ok())
+                .ok_or("date out of range")?;
+            vec![
+                (ifd, Tag::GPS_DATE_STAMP, Some(date)),
+                (ifd, tag, Some(time)),
+            ]
+        }
+        Kind::Flash => {
+            let flash = match property {
+                Property::Struct(_) => flash_fields(property),
+                _ => text()?.trim().parse().ok(),
+            };
+            let flash = flash.ok_or("expected a flash value or structure")?;
+            vec![(ifd, tag, Some(Value::U16(vec![flash])))]
+        }
+    };
+    // the type of every value is checked here, as applying is not undone
+    for (ifd, tag, value) in &changes {
+        if let Some(value) = value {
+            if !allows_data_type(*tag, (*ifd).into(), value.data_type()) {
+                return Err("value type not allowed for the tag");
+            }
+        }
+    }
+    Ok(changes)
+}
+
+/// Combine the fields of a `Flash` structure into the value of the Flash tag.
+fn flash_fields(property: &Property) -> Option<u16> {
+    let boolean = |name: &str| match property.field(name).map(str::trim) {
+        None => Some(0),
+        Some(value) if value.eq_ignore_ascii_case("true") => Some(1),
+        Some(value) if value.eq_ignore_ascii_case("false") => Some(0),
+        Some(_) => None,
+    };
+    let number = |name: &str| match property.field(name) {
+        None => Some(0),
+        Some(value) => value.trim().parse::<u16>().ok().filter(|n| *n < 4),
+    };
+    Some(
+        boolean("Fired")?
+            | (number("Return")? << 1)
+            | (number("Mode")? << 3)
+            | (boolean("Function")? << 5)
+            | (boolean("RedEyeMode")? << 6),
+    )
+}
+
+/// Return text without trailing spaces and NUL characters, or `None` if
+/// nothing is left.
+fn text(value: Value) -> Option<String> {
+    match value {
+        Value::Text(text) => {
+            let text = text.trim_end_matches([' ', '\0']);
+            (!text.is_empty()).then(|| text.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn integers(value: Value) -> Option<Vec<i64>> {
+    let numbers: Vec<i64> = match value {
+        Value::U8(v) | Value::Undefined(v) => v.into_iter().map(i64::from).collect(),
+        Value::I8(v) => v.into_iter().map(i64::from).collect(),
+        Value::U16(v) => v.into_iter().map(i64::from).collect(),
+        Value::I16(v) => v.into_iter().map(i64::from).collect(),
+        Value::U32(v) => v.into_iter().map(i64::from).collect(),
+        Value::I32(v) => v.into_iter().map(i64::from).collect(),
+        _ => return None,
+    };
+    (!numbers.is_empty()).then_some(numbers)
+}
+
+fn rationals(value: Value) -> Option<Vec<String>> {
+    let rationals: Vec<String> = match value {
+        Value::URational(v) => v.iter().map(ToString::to_string).collect(),
+        Value::IRational(v) => v.iter().map(ToString::to_string).collect(),
+        _ => return None,
+    };
+    (!rationals.is_empty()).then_some(rationals)
+}
+
+fn integer_value(numbers: &[i64], data_type: DataType) -> Option<Value> {
+    fn convert<T: TryFrom<i64>>(numbers: &[i64]) -> Option<Vec<T>> {
+        numbers.iter().map(|n| T::try_from(*n).ok()).collect()
+    }
+    Some(match data_type {
+        DataType::U8 => Value::U8(convert(numbers)?),
+        DataType::I8 => Value::I8(convert(numbers)?),
+        DataType::U16 => Value::U16(convert(numbers)?),
+        DataType::I16 => Value::I16(convert(numbers)?),
+        DataType::U32 => Value::U32(convert(numbers)?),
+        DataType::I32 => Value::I32(convert(numbers)?),
+        DataType::Undefined => Value::Undefined(convert(numbers)?),
+        _ => return None,
+    })
+}
+
+fn rational_value(rationals: &[(i64, i64)], data_type: DataType) -> Option<Value> {
+    Some(match data_type {
+        DataType::URational => Value::URational(
+            rationals
+                .iter()
+                .map(|(n, d)| Some(Rational(u32::try_from(*n).ok()?, u32::try_from(*d).ok()?)))
+                .collect::<Option<_>>()?,
+        ),
+        DataType::IRational => Value::IRational(
+            rationals
+                .iter()
+                .map(|(n, d)| Some(Rational(i32::try_from(*n).ok()?, i32::try_from(*d).ok()?)))
+                .collect::<Option<_>>()?,
+        ),
+        _ => return None,
+    })
+}
+
+/// Convert a coordinate written as `DDD,MM,SSk` or `DDD,MM.mmk` to decimal
+/// degrees, negative if `k` is the second hemisphere.
+fn parse_coordinate(text: &str, hemispheres: [&str; 2]) -> Option<f64> {
+    let text = text.trim();
+    let (index, _) = text.char_indices().next_back()?;
+    let (numbers, hemisphere) = text.split_at(index);
+    let sign = match hemispheres.iter().position(|other| *other == hemisphere)? {
+        0 => 1.0,
+        _ => -1.0,
+    };
+    let mut degrees = 0.0;
+    let mut unit = 1.0;
+    let mut count = 0;
+    for part in numbers.split(',') {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+            return None;
+        }
+        degrees += part.parse::<f64>().ok()? / unit;
+        unit *= 60.0;
+        count += 1;
+    }
+    if !(2..=3).contains(&count) {
+        return None;
+    }
+    Some(sign * degrees)
+}
+
+/// Parse the `YYYY:MM:DD` form of EXIF dates.
+fn exif_date(text: &str) -> Option<(u32, u32, u32)> {
+    let mut fields = text.trim().split(':');
+    let date = (
+        number(fields.next()?, 4)?,
+        number(fields.next()?, 2)?,
+        number(fields.next()?, 2)?,
+    );
+    fields.next().is_none().then_some(date)
+}
+
+/// Parse a field with exactly the given number of digits.
+fn number(text: &str, digits: usize) -> Option<u32> {
+    if text.len() != digits || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Date and time as written in XMP.
+struct XmpDate {
+    year: u32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    /// Digits of the fraction of the second.
+    fraction: String,
+    /// Offset from UTC in minutes.
+    offset: Option<i32>,
+}
+
+impl XmpDate {
+    /// Parse an ISO 8601 date such as `2024-02-29T13:45:30.25+01:00`. The
+    /// month, day, time, seconds, fraction and offset may be left out.
+    fn parse(text: &str) -> Option<XmpDate> {
+        let text = text.trim();
+        let (date, time) = text.split_once('T').unwrap_or((text, ""));
+        let mut fields = date.split('-');
+        let year = number(fields.next()?, 4)?;
+        let month = fields.next().map_or(Some(1), |field| number(field, 2))?;
+        let day = fields.next().map_or(Some(1), |field| number(field, 2))?;
+        if fields.next().is_some() {
+            return None;
+        }
+        let (time, offset) = match time.find(['Z', '+', '-']) {
+            Some(index) => (&time[..index], Some(parse_offset(&time[index..])?)),
+            None => (time, None),
+        };
+        let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+        if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let (hour, minute, second) = if time.is_empty() {
+            (0, 0, 0)
+        } else {
+            let mut fields = time.split(':');
+            let hour = number(fields.next()?, 2)?;
+            let minute = number(fields.next()?, 2)?;
+            let second = fields.next().map_or(Some(0), |field| number(field, 2))?;
+            if fields.next().is_some() {
+                return None;
+            }
+            (hour, minute, second)
+        };
+        XmpDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            fraction: fraction.to_string(),
+            offset,
+        }
+        .validated()
+    }
+
+    /// Parse the `YYYY:MM:DD HH:MM:SS` form of EXIF dates.
+    fn from_exif(text: &str) -> Option<XmpDate> {
+        let (date, time) = text.trim_end_matches([' ', '\0']).split_once(' ')?;
+        let (year, month, day) = exif_date(date)?;
+        let mut fields = time.split(':');
+        let (hour, minute, second) = (
+            number(fields.next()?, 2)?,
+            number(fields.next()?, 2)?,
+            number(fields.next()?, 2)?,
+        );
+        if fields.next().is_some() {
+            return None;
+        }
+        XmpDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            fraction: String::new(),
+            offset: None,
+        }
+        .validated()
+    }
+
+    fn validated(self) -> Option<XmpDate> {
+        let valid = (1..=12).contains(&self.month)
+            && (1..=31).contains(&self.day)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60;
+        valid.then_some(self)
+    }
+
+    /// Format the date and time in the `YYYY:MM:DD HH:MM:SS` form of EXIF.
+    fn to_exif(&self) -> String {
+        format!(
+            "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+
+    /// Return the point in time, assuming UTC if there is no offset.
+    fn to_system_time(&self) -> Option<SystemTime> {
+        let seconds = i64::from(self.hour * 3600 + self.minute * 60 + self.second)
+            - i64::from(self.offset.unwrap_or(0)) * 60;
+        let nanos: u32 = format!("{:0<9.9}", self.fraction).parse().ok()?;
+        system_time_from_civil(
+            i64::from(self.year),
+            i64::from(self.month),
+            i64::from(self.day),
+            seconds,
+            nanos,
+        )
+    }
+}
+
+impl Display for XmpDate {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if !self.fraction.is_empty() {
+            write!(fmt, ".{}", self.fraction)?;
+        }
+        match self.offset {
+            Some(0) => fmt.write_str("Z"),
+            Some(minutes) => fmt.write_str(&format_offset(minutes)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// XMP packet holding a single description of the given properties.
+struct Packet<'a>(&'a [(&'a str, Property)]);
+
+impl<'a> Display for Packet<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        writeln!(
+            fmt,
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>"
+        )?;
+        writeln!(fmt, "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">")?;
+        writeln!(fmt, " <rdf:RDF xmlns:rdf=\"{}\">", RDF)?;
+        write!(fmt, "  <rdf:Description rdf:about=\"\"")?;
+        for (prefix, uri) in NAMESPACES {
+            write!(fmt, "\n    xmlns:{}=\"{}\"", prefix, uri)?;
+        }
+        writeln!(fmt, ">")?;
+        for (name, property) in self.0 {
+            match property {
+                Property::Simple(text) => writeln!(fmt, "   <{0}>{1}</{0}>", name, escape(text))?,
+                Property::Seq(items) => {
+                    writeln!(fmt, "   <{}>", name)?;
+                    writeln!(fmt, "    <rdf:Seq>")?;
+                    for item in items {
+                        writeln!(fmt, "     <rdf:li>{}</rdf:li>", escape(item))?;
+                    }
+                    writeln!(fmt, "    </rdf:Seq>")?;
+                    writeln!(fmt, "   </{}>", name)?;
+                }
+                Property::Alt(text) => {
+                    writeln!(fmt, "   <{}>", name)?;
+                    writeln!(fmt, "    <rdf:Alt>")?;
+                    writeln!(
+                        fmt,
+                        "     <rdf:li xml:lang=\"x-default\">{}</rdf:li>",
+                        escape(text)
+                    )?;
+                    writeln!(fmt, "    </rdf:Alt>")?;
+                    writeln!(fmt, "   </{}>", name)?;
+                }
+                Property::Struct(fields) => {
+                    let (prefix, _) = name.split_once(':').unwrap_or(("", name));
+                    writeln!(fmt, "   <{} rdf:parseType=\"Resource\">", name)?;
+                    for (field, value) in fields {
+                        writeln!(
+                            fmt,
+                            "    <{0}:{1}>{2}</{0}:{1}>",
+                            prefix,
+                            field,
+                            escape(value)
+                        )?;
+                    }
+                    writeln!(fmt, "   </{}>", name)?;
+                }
+            }
+        }
+        writeln!(fmt, "  </rdf:Description>")?;
+        writeln!(fmt, " </rdf:RDF>")?;
+        writeln!(fmt, "</x:xmpmeta>")?;
+        write!(fmt, "<?xpacket end=\"w\"?>")
+    }
+}
+
+/// Element of a parsed packet, with the namespaces of its name and the names
+/// of its attributes resolved.
+#[derive(Default)]
+struct Element {
+    namespace: String,
+    name: String,
+    attributes: Vec<(String, String, String)>,
+    text: String,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+
+    fn attribute(&self, namespace: &str, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(other_namespace, other_name, _)| {
+                other_namespace == namespace && other_name == name
+            })
+            .map(|(_, _, value)| value.as_str())
+    }
+
+    /// Interpret the element as the value of a property.
+    fn property(&self) -> Property {
+        if self.attribute(RDF, "parseType") == Some("Resource") {
+            return Property::Struct(self.fields());
+        }
+        for child in &self.children {
+            let items = || child.children.iter().filter(|item| item.is(RDF, "li"));
+            if child.is(RDF, "Seq") || child.is(RDF, "Bag") {
+                return Property::Seq(items().map(|item| item.text.clone()).collect());
+            }
+            if child.is(RDF, "Alt") {
+                let default = items()
+                    .find(|item| item.attribute(XML, "lang") == Some("x-default"))
+                    .or_else(|| items().next());
+                return Property::Alt(default.map_or_else(String::new, |item| item.text.clone()));
+            }
+            if child.is(RDF, "Description") {
+                return Property::Struct(child.fields());
+            }
+        }
+        Property::Simple(self.text.clone())
+    }
+
+    /// Return the fields of a structure, written as attributes or elements.
+    fn fields(&self) -> Vec<(String, String)> {
+        let attributes = self
+            .attributes
+            .iter()
+            .filter(|(namespace, _, _)| namespace != RDF)
+            .map(|(_, name, value)| (name.clone(), value.clone()));
+        let elements = self
+            .children
+            .iter()
+            .map(|child| (child.name.clone(), child.text.clone()));
+        attributes.chain(elements).collect()
+    }
+}
+
+/// Parse a packet into a tree of elements below a nameless root.
+fn parse(packet: &str) -> Result<Element, super::Error> {
+    let malformed = |err: quick_xml::Error| super::Error::InvalidXmp(err.to_string());
+    let namespace = |result: ResolveResult| match result {
+        ResolveResult::Bound(namespace) => String::from_utf8_lossy(namespace.as_ref()).into_owned(),
+        _ => String::new(),
+    };
+    let mut reader = NsReader::from_str(packet);
+    reader.trim_text(true).expand_empty_elements(true);
+    let mut stack = vec![Element::default()];
+    loop {
+        let (resolved, event) = reader.read_resolved_event().map_err(malformed)?;
+        let element_namespace = namespace(resolved);
+        match event {
+            Event::Start(start) => {
+                let mut element = Element {
+                    namespace: element_namespace,
+                    name: std::str::from_utf8(start.local_name().as_ref())?.to_string(),
+                    ..Element::default()
+                };
+                for attribute in start.attributes() {
+                    let attribute = attribute.map_err(|err| malformed(err.into()))?;
+                    let (resolved, name) = reader.resolve_attribute(attribute.key);
+                    let value = attribute.unescape_value().map_err(malformed)?;
+                    element.attributes.push((
+                        namespace(resolved),
+                        std::str::from_utf8(name.as_ref())?.to_string(),
+                        value.into_owned(),
+                    ));
+                }
+                stack.push(element);
+            }
+            Event::End(_) => {
+                let element = stack.pop().filter(|_| !stack.is_empty());
+                let (Some(element), Some(parent)) = (element, stack.last_mut()) else {
+                    return Err(super::Error::InvalidXmp("unexpected end tag".into()));
+                };
+                parent.children.push(element);
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(malformed)?;
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                let text = std::str::from_utf8(&data)?;
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(text);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    match stack.pop() {
+        Some(root) if stack.is_empty() => Ok(root),
+        _ => Err(super::Error::InvalidXmp("unclosed element".into())),
+    }
+}
+
+/// Gather the namespace, local name and value of the properties of every
+/// `rdf:Description`.
+fn collect<'a>(element: &'a Element, properties: &mut Vec<(&'a str, &'a str, Property)>) {
+    if element.is(RDF, "Description") {
+        for (namespace, name, value) in &element.attributes {
+            properties.push((namespace, name, Property::Simple(value.clone())));
+        }
+        for child in &element.children {
+            properties.push((&child.namespace, &child.name, child.property()));
+        }
+    } else {
+        for child in &element.children {
+            collect(child, properties);
+        }
+    }
+}